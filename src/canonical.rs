@@ -0,0 +1,49 @@
+//! Canonical comparison keys for subscription deduplication.
+//!
+//! A large subscription often lists the same server many times with only a different tag/remark
+//! or query-parameter ordering; naive `PartialEq`/`to_link` comparison treats those as distinct
+//! endpoints. [`crate::Protocol::canonical_key`] instead builds a comparison-only string —
+//! lowercase/Punycode host, sorted query parameters with values at their protocol default
+//! omitted, fragment/remark dropped entirely — so callers can dedup or group endpoints by real
+//! identity. Modeled on rust-url's origin/normalization concept: a canonical key is for
+//! comparison only and is not guaranteed to parse back into a `Protocol`; use
+//! [`crate::Protocol::normalized`] for a cleaned clone that still round-trips through `to_link`.
+
+use crate::host::Host;
+
+/// `(query key, default value)` pairs omitted from a canonical key: each one is the value a
+/// protocol's own parser treats as equivalent to the parameter being absent.
+const DEFAULT_PARAMS: &[(&str, &str)] = &[
+    ("security", "none"),
+    ("encryption", "none"),
+    ("type", "tcp"),
+    ("net", "tcp"),
+];
+
+/// Lowercases and Punycode-normalizes a host for comparison. Falls back to a plain lowercase
+/// copy if it isn't a valid IDNA host, since building a canonical key must never fail.
+pub(crate) fn normalize_host(host: &str) -> String {
+    Host::parse(host)
+        .to_ascii()
+        .unwrap_or_else(|_| host.to_string())
+        .to_lowercase()
+}
+
+/// Builds a canonical key of the form `scheme://host:port?k1=v1&k2=v2`: parameters at their
+/// protocol default (see [`DEFAULT_PARAMS`]) are dropped, and the rest are sorted by key.
+pub(crate) fn build_key(scheme: &str, host: &str, port: u16, mut params: Vec<(&str, String)>) -> String {
+    params.retain(|(k, v)| !DEFAULT_PARAMS.contains(&(*k, v.as_str())));
+    params.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut key = format!("{scheme}://{host}:{port}");
+    if !params.is_empty() {
+        let query = params
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("&");
+        key.push('?');
+        key.push_str(&query);
+    }
+    key
+}
@@ -0,0 +1,25 @@
+//! Shared query-string codec used by every protocol's `to_link`.
+//!
+//! Every parser in this crate decodes its query string with `url::form_urlencoded::parse`,
+//! which treats `+` as an encoded space. Building the query string back up with a plain
+//! percent-encoder only happens to round-trip because a decoded space happens to re-encode to
+//! `%20`. [`encode_query`] serializes with the same `application/x-www-form-urlencoded` rules
+//! `form_urlencoded::parse` expects, so every `to_link` produces a query string that is
+//! byte-compatible with what a WHATWG-compliant client would emit for the same values.
+//!
+//! Userinfo components (passwords) and fragments (remarks/tags) are percent-encoded with
+//! `urlencoding::encode` directly at their call sites instead, since they aren't
+//! `application/x-www-form-urlencoded` query values and use a different reserved-character set.
+
+/// Serializes `pairs` as `application/x-www-form-urlencoded`, matching how this crate's parsers
+/// decode query strings via `url::form_urlencoded::parse`.
+pub(crate) fn encode_query<'a, I>(pairs: I) -> String
+where
+    I: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+    for (key, value) in pairs {
+        serializer.append_pair(key, value);
+    }
+    serializer.finish()
+}
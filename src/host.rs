@@ -0,0 +1,178 @@
+//! Shared parsed-host representation used across protocol parsers.
+//!
+//! Distinguishes a domain name from an IPv4/IPv6 literal so that bracketed IPv6 hosts
+//! (`[::1]:443`) round-trip correctly through `to_link`, and provides IDNA conversion so
+//! internationalized domain names can be normalized to ASCII (and back) consistently.
+//!
+//! [`validate_host`] additionally offers strict classification/validation (rejecting an empty
+//! host, a malformed label, or an invalid-looking IPv4 literal) for protocols' `validate`/
+//! `parse_strict` methods.
+
+use crate::constants::error_msg;
+use crate::error::{ProtocolError, Result};
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// A parsed host: a domain name, an IPv4 literal, or an IPv6 literal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Host {
+    /// A domain name (or anything that isn't a valid IPv4/IPv6 literal).
+    Domain(String),
+    /// An IPv4 literal.
+    Ipv4(Ipv4Addr),
+    /// An IPv6 literal.
+    Ipv6(Ipv6Addr),
+}
+
+impl Host {
+    /// Parses a bare host string (no brackets, no port) into a [`Host`].
+    ///
+    /// IPv4/IPv6 literals are detected by attempting to parse as such; anything else is
+    /// treated as a domain name.
+    pub fn parse(host: &str) -> Self {
+        if let Ok(ip) = host.parse::<Ipv4Addr>() {
+            Host::Ipv4(ip)
+        } else if let Ok(ip) = host.parse::<Ipv6Addr>() {
+            Host::Ipv6(ip)
+        } else {
+            Host::Domain(host.to_string())
+        }
+    }
+
+    /// Converts a domain host to its ASCII (Punycode) form via IDNA, using the same pipeline
+    /// the `url` crate applies when parsing a URL's authority. IP literals are returned
+    /// formatted as-is; they have no IDNA form.
+    ///
+    /// Errors with [`ProtocolError::InvalidField`] if the domain isn't a valid IDNA host.
+    pub fn to_ascii(&self) -> Result<String> {
+        match self {
+            Host::Domain(d) => match url::Host::parse(d) {
+                Ok(url::Host::Domain(ascii)) => Ok(ascii),
+                Ok(other) => Ok(other.to_string()),
+                Err(e) => Err(ProtocolError::InvalidField(format!(
+                    "Invalid IDNA host {}: {}",
+                    d, e
+                ))),
+            },
+            Host::Ipv4(ip) => Ok(ip.to_string()),
+            Host::Ipv6(ip) => Ok(ip.to_string()),
+        }
+    }
+
+    /// Converts an ASCII/Punycode (`xn--`) domain host back to its Unicode display form.
+    /// IP literals and domains with no Punycode labels are returned unchanged.
+    pub fn to_unicode(&self) -> String {
+        match self {
+            Host::Domain(d) => idna::domain_to_unicode(d).0,
+            Host::Ipv4(ip) => ip.to_string(),
+            Host::Ipv6(ip) => ip.to_string(),
+        }
+    }
+}
+
+/// Strict classification of a host, returned by [`validate_host`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostKind {
+    /// An IPv4 literal.
+    Ipv4,
+    /// An IPv6 literal.
+    Ipv6,
+    /// A domain name.
+    Domain,
+}
+
+/// Strictly classifies and validates a bare host string (no brackets, no port), rejecting
+/// malformed authorities that [`Host::parse`]'s best-effort classification lets through: an
+/// empty host, an empty label (e.g. the trailing one in `example.com..`), a label longer than
+/// 63 bytes, a domain that doesn't normalize to ASCII via IDNA, and a dotted-quad-shaped string
+/// that isn't a valid IPv4 literal (e.g. `1.2.3.999`).
+///
+/// # Errors
+///
+/// Returns `ProtocolError::InvalidField` describing the first check that fails.
+pub fn validate_host(host: &str) -> Result<HostKind> {
+    if host.is_empty() {
+        return Err(ProtocolError::InvalidField("host must not be empty".to_string()));
+    }
+    if host.parse::<Ipv4Addr>().is_ok() {
+        return Ok(HostKind::Ipv4);
+    }
+    if host.parse::<Ipv6Addr>().is_ok() {
+        return Ok(HostKind::Ipv6);
+    }
+    if looks_like_dotted_quad(host) {
+        return Err(ProtocolError::InvalidField(format!(
+            "host looks like an IPv4 literal but isn't valid: {}",
+            host
+        )));
+    }
+    for label in host.split('.') {
+        if label.is_empty() {
+            return Err(ProtocolError::InvalidField(format!(
+                "host has an empty label: {}",
+                host
+            )));
+        }
+        if label.len() > 63 {
+            return Err(ProtocolError::InvalidField(format!(
+                "host label exceeds 63 bytes: {}",
+                label
+            )));
+        }
+    }
+    let ascii = Host::Domain(host.to_string()).to_ascii()?;
+    if !ascii.is_ascii() {
+        return Err(ProtocolError::InvalidField(format!(
+            "host did not normalize to ASCII via IDNA: {}",
+            host
+        )));
+    }
+    Ok(HostKind::Domain)
+}
+
+/// Returns `true` if `host` is shaped like a dotted-quad (four dot-separated all-digit groups)
+/// without already having parsed as a valid [`Ipv4Addr`].
+fn looks_like_dotted_quad(host: &str) -> bool {
+    let parts: Vec<&str> = host.split('.').collect();
+    parts.len() == 4 && parts.iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+}
+
+impl fmt::Display for Host {
+    /// Formats the host the way it appears in a link: IPv6 literals are bracketed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Domain(d) => write!(f, "{}", d),
+            Host::Ipv4(ip) => write!(f, "{}", ip),
+            Host::Ipv6(ip) => write!(f, "[{}]", ip),
+        }
+    }
+}
+
+/// Splits a `host:port` segment, treating a leading `[...]` as a bracketed IPv6 literal.
+///
+/// A bracketed literal must be followed immediately by `:port`, with nothing else trailing
+/// the closing bracket; otherwise the split falls back to the last `:` so domain/IPv4 hosts
+/// continue to work. An unbracketed IPv6 literal gets no special treatment: it is split at its
+/// last `:` like any other host, so its final group is taken as the port.
+pub fn split_host_port(host_port: &str) -> Result<(Host, &str)> {
+    if let Some(rest) = host_port.strip_prefix('[') {
+        let close_pos = rest
+            .find(']')
+            .ok_or_else(|| ProtocolError::InvalidFormat("Unterminated IPv6 literal".to_string()))?;
+        let address = &rest[..close_pos];
+        let ip: Ipv6Addr = address
+            .parse()
+            .map_err(|e| ProtocolError::InvalidField(format!("Invalid IPv6 address: {}", e)))?;
+
+        let after_bracket = &rest[close_pos + 1..];
+        let port_str = after_bracket
+            .strip_prefix(':')
+            .ok_or_else(|| ProtocolError::InvalidFormat(error_msg::MISSING_COLON_HOST_PORT.to_string()))?;
+        Ok((Host::Ipv6(ip), port_str))
+    } else {
+        let colon_pos = host_port
+            .rfind(':')
+            .ok_or_else(|| ProtocolError::InvalidFormat(error_msg::MISSING_COLON_HOST_PORT.to_string()))?;
+        Ok((Host::parse(&host_port[..colon_pos]), &host_port[colon_pos + 1..]))
+    }
+}
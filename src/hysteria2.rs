@@ -4,9 +4,11 @@
 //!
 //! **Scheme**: This crate uses `hysteria2://` (not `hy2`).
 //!
-//! **auth**: Optional; if present, format is typically `username:password`; special characters must be URL-encoded.
+//! **auth**: Optional; if present, format is typically `username:password`; special characters must be URL-encoded. The two halves are split on the first unencoded `:` and percent-decoded independently into `username` and `password`.
 //!
-//! **hostname:port**: Port is required by this crate and must be a valid u16.
+//! **hostname:port**: Port is required by this crate and must be a valid u16, or a port-hopping
+//! spec (comma-separated single ports and/or inclusive `low-high` ranges, e.g. `443-600` or
+//! `443,500-600`).
 //!
 //! **Query** (official): `obfs` (e.g. salamander), `obfs-password`, `sni`, `insecure` (1/0), `pinSHA256`.
 //!
@@ -18,30 +20,122 @@
 //!
 //! 1. Prefix `hysteria2://` is case-insensitive.
 //! 2. Fragment and query are split by `#` and `?`; main part is `[auth@]host:port`.
-//! 3. If `@` is present, the part before it is the password (URL-decoded); otherwise no password.
+//! 3. If `@` is present, the userinfo before it is split into `username` and `password` on the
+//!    first `:` (per rule 6) and each half is percent-decoded independently; otherwise both are
+//!    `None`.
 //! 4. Port is required and must parse as u16; otherwise `InvalidField`.
+//! 5. An IPv6 literal host is written bracketed (`[::1]:443`); [`crate::host::split_host_port`]
+//!    strips and validates the brackets on parse, and `to_link` re-adds them when `host` parses
+//!    as IPv6.
+//! 6. Userinfo, query, and fragment are extracted via [`url::Url`]. The query is decoded with
+//!    its form-urlencoded `query_pairs`; username, password, and fragment are percent-decoded
+//!    individually, keeping the raw text when the decoded bytes aren't valid UTF-8. Host/port are still
+//!    derived from the shared bracket-aware splitter first, so a malformed port or IPv6 literal
+//!    surfaces this crate's own `InvalidFormat`/`InvalidField` rather than a generic URL error.
+//! 7. The port segment accepts a port-hopping spec: comma-separated tokens, each a single `u16`
+//!    or an inclusive `low-high` range (`low <= high`, both `u16`). [`Hysteria2Config::port`]
+//!    always holds the first concrete port; [`Hysteria2Config::ports`] holds the full parsed
+//!    spec and is only `Some` when it's more than a single bare port.
+//! 8. `host` and `sni` are normalized to ASCII (Punycode) via IDNA on parse, so they match real
+//!    clients' TLS handshakes and compare reliably; the original Unicode form is available via
+//!    [`Hysteria2::host_unicode`]/[`Hysteria2::sni_unicode`], and [`Hysteria2::to_link_idna`]
+//!    guarantees ASCII-only output even for a directly-constructed config. If the link has no
+//!    `sni`/`peer` query parameter, `sni` defaults to this ASCII host, matching how real clients
+//!    derive SNI.
+//! 9. `to_link` serializes the query string with the shared `application/x-www-form-urlencoded`
+//!    codec (`crate::codec::encode_query`), the exact inverse of how it's decoded.
+//! 10. [`Hysteria2::parse_strict`] additionally calls [`Hysteria2::validate`], which classifies
+//!     `host` with [`Hysteria2::host_kind`], rejecting a malformed authority (empty host, an
+//!     empty label, a label over 63 bytes, or an invalid-looking IPv4 literal such as
+//!     `1.2.3.999`).
 
 use crate::ProtocolParser;
+use crate::canonical;
 use crate::constants::{error_msg, scheme};
 use crate::error::{ProtocolError, Result};
+use crate::host::{self, Host};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use url::Url;
+
+/// Percent-decodes `s`, falling back to the raw text unchanged if it isn't validly encoded —
+/// the same leniency this crate's other parsers fall back to.
+fn percent_decode(s: &str) -> String {
+    urlencoding::decode(s)
+        .map(|c| c.into_owned())
+        .unwrap_or_else(|_| s.to_string())
+}
+
+/// A single token of a Hysteria2 port-hopping spec: either a bare port or an inclusive range.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PortRange {
+    /// Lower bound (inclusive); equal to `high` for a bare port.
+    pub low: u16,
+    /// Upper bound (inclusive).
+    pub high: u16,
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.low == self.high {
+            write!(f, "{}", self.low)
+        } else {
+            write!(f, "{}-{}", self.low, self.high)
+        }
+    }
+}
+
+/// Parses a comma-separated port-hopping spec into its individual ranges.
+fn parse_port_ranges(spec: &str) -> Result<Vec<PortRange>> {
+    spec.split(',')
+        .map(|token| {
+            if let Some((low_str, high_str)) = token.split_once('-') {
+                let low: u16 = low_str.parse().map_err(|e| {
+                    ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e))
+                })?;
+                let high: u16 = high_str.parse().map_err(|e| {
+                    ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e))
+                })?;
+                if low > high {
+                    return Err(ProtocolError::InvalidField(format!(
+                        "Invalid port range: {} > {}",
+                        low, high
+                    )));
+                }
+                Ok(PortRange { low, high })
+            } else {
+                let port: u16 = token.parse().map_err(|e| {
+                    ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e))
+                })?;
+                Ok(PortRange {
+                    low: port,
+                    high: port,
+                })
+            }
+        })
+        .collect()
+}
 
 /// Hysteria2 configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Hysteria2Config {
     /// Server host
     pub host: String,
-    /// Server port
+    /// Server port; the first concrete port when `ports` describes a hop spec
     pub port: u16,
+    /// Full port-hopping spec (single ports and/or ranges), if the link specified more than a
+    /// single bare port
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports: Option<Vec<PortRange>>,
+    /// Username, if the userinfo contains a `username:password` pair (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
     /// Password (optional)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
     /// Protocol (udp, wechat-video, faketcp, etc.)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub protocol: Option<String>,
-    /// Authentication string
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub auth: Option<String>,
     /// ALPN settings
     #[serde(skip_serializing_if = "Option::is_none")]
     pub alpn: Option<Vec<String>>,
@@ -87,6 +181,130 @@ pub struct Hysteria2 {
     pub config: Hysteria2Config,
 }
 
+impl Hysteria2 {
+    /// Classifies `host` as an IPv4 literal, IPv6 literal, or domain name, rejecting a
+    /// malformed authority (see [`crate::host::validate_host`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if `host` is neither a valid IP literal nor a
+    /// valid domain name.
+    pub fn host_kind(&self) -> Result<host::HostKind> {
+        host::validate_host(&self.config.host)
+    }
+
+    /// Parses a Hysteria2 link and additionally checks it with [`Hysteria2::validate`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if the link format is invalid or unsupported, or
+    /// `ProtocolError::InvalidField` if it fails semantic validation.
+    pub fn parse_strict(link: &str) -> Result<Self> {
+        let hysteria2 = Self::parse(link)?;
+        hysteria2.validate()?;
+        Ok(hysteria2)
+    }
+
+    /// Checks semantic correctness beyond what `parse` enforces structurally: `host`
+    /// classifies as a valid IP literal or domain name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` describing the first check that fails.
+    pub fn validate(&self) -> Result<()> {
+        self.host_kind()?;
+        Ok(())
+    }
+
+    /// Returns `host` in its Unicode display form (reversing IDNA Punycode), unchanged if it has
+    /// no Punycode labels.
+    pub fn host_unicode(&self) -> String {
+        Host::parse(&self.config.host).to_unicode()
+    }
+
+    /// Returns `sni` in its Unicode display form (reversing IDNA Punycode), if set.
+    pub fn sni_unicode(&self) -> Option<String> {
+        self.config
+            .sni
+            .as_deref()
+            .map(|s| Host::parse(s).to_unicode())
+    }
+
+    /// Generates a link like [`ProtocolParser::to_link`], but first normalizes `host` and `sni`
+    /// to ASCII so the output is guaranteed ASCII-only even if the configuration was built
+    /// directly (rather than via `parse`, which already normalizes these fields).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if `host` or `sni` isn't a valid IDNA host, or other
+    /// `ProtocolError` variants if the configuration cannot be serialized.
+    pub fn to_link_idna(&self) -> Result<String> {
+        let mut config = self.config.clone();
+        normalize_idna_fields(&mut config)?;
+        Hysteria2 { config }.to_link()
+    }
+
+    /// Builds a stable, comparison-only key: see [`crate::Protocol::canonical_key`].
+    /// `username`/`password` are included since they select a different credential on the same
+    /// server; `fragment` (remark) is dropped.
+    pub fn canonical_key(&self) -> String {
+        let host = canonical::normalize_host(&self.config.host);
+        let mut params: Vec<(&str, String)> = Vec::new();
+        if let Some(ref ports) = self.config.ports {
+            params.push((
+                "ports",
+                ports
+                    .iter()
+                    .map(|p| p.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ));
+        }
+        if let Some(ref username) = self.config.username {
+            params.push(("username", username.clone()));
+        }
+        if let Some(ref password) = self.config.password {
+            params.push(("password", password.clone()));
+        }
+        if let Some(ref protocol) = self.config.protocol {
+            params.push(("protocol", protocol.clone()));
+        }
+        if let Some(ref alpn) = self.config.alpn {
+            params.push(("alpn", alpn.join(",")));
+        }
+        if let Some(ref sni) = self.config.sni {
+            params.push(("sni", canonical::normalize_host(sni)));
+        }
+        if let Some(insecure) = self.config.insecure {
+            params.push(("insecure", insecure.to_string()));
+        }
+        if let Some(ref obfs) = self.config.obfs {
+            params.push(("obfs", obfs.clone()));
+        }
+        canonical::build_key("hysteria2", &host, self.config.port, params)
+    }
+
+    /// Returns a cleaned clone: `host`/`sni` normalized to ASCII/Punycode and lowercased,
+    /// `fragment` (remark) cleared. Unlike [`Hysteria2::canonical_key`] the result is still a
+    /// valid, parseable [`Hysteria2`].
+    pub fn normalized(&self) -> Self {
+        let mut config = self.config.clone();
+        config.host = canonical::normalize_host(&config.host);
+        config.sni = config.sni.as_deref().map(canonical::normalize_host);
+        config.fragment = None;
+        Hysteria2 { config }
+    }
+}
+
+/// Normalizes `host` and `sni` to their ASCII/Punycode form via IDNA.
+fn normalize_idna_fields(config: &mut Hysteria2Config) -> Result<()> {
+    config.host = Host::parse(&config.host).to_ascii()?;
+    if let Some(ref sni) = config.sni {
+        config.sni = Some(Host::parse(sni).to_ascii()?);
+    }
+    Ok(())
+}
+
 impl ProtocolParser for Hysteria2 {
     fn parse(link: &str) -> Result<Self> {
         if !link.to_lowercase().starts_with(scheme::HYSTERIA2) {
@@ -99,57 +317,56 @@ impl ProtocolParser for Hysteria2 {
 
         let link_body = &link[scheme::HYSTERIA2.len()..];
 
-        // Split into parts: [password@]host:port[?query][#fragment]
-        let (main_part, query_part, fragment) = {
-            let hash_pos = link_body.find('#');
-            let (before_hash, fragment) = if let Some(pos) = hash_pos {
-                let frag_str = &link_body[pos + 1..];
-                let decoded_frag = urlencoding::decode(frag_str).map_err(|e| {
-                    ProtocolError::UrlParseError(format!("Failed to decode fragment: {}", e))
-                })?;
-                (&link_body[..pos], Some(decoded_frag.to_string()))
-            } else {
-                (link_body, None)
-            };
-
-            let query_pos = before_hash.find('?');
-            let (main, query) = if let Some(pos) = query_pos {
-                (&before_hash[..pos], Some(&before_hash[pos + 1..]))
-            } else {
-                (before_hash, None)
-            };
-
-            (main, query, fragment)
+        // Host/port come from the pre-query/fragment segment via the shared bracket-aware
+        // splitter, so a bad port or malformed IPv6 literal fails fast with this crate's own
+        // error variants before we ever hand the link to `url::Url`.
+        let before_hash = link_body.find('#').map_or(link_body, |p| &link_body[..p]);
+        let main_part = before_hash.find('?').map_or(before_hash, |p| &before_hash[..p]);
+        let (userinfo, host_port) = match main_part.split_once('@') {
+            Some((u, hp)) => (Some(u), hp),
+            None => (None, main_part),
         };
 
-        // Parse main part: [password@]host:port
-        let (password, host_port) = if let Some(at_pos) = main_part.find('@') {
-            let pass = &main_part[..at_pos];
-            let decoded_pass = urlencoding::decode(pass).map_err(|e| {
-                ProtocolError::UrlParseError(format!("Failed to decode password: {}", e))
-            })?;
-            (Some(decoded_pass.to_string()), &main_part[at_pos + 1..])
+        let (host, port_str) = host::split_host_port(host_port)?;
+        let port_ranges = parse_port_ranges(port_str)?;
+        let port = port_ranges[0].low;
+        let ports = if port_ranges.len() == 1 && port_ranges[0].low == port_ranges[0].high {
+            None
         } else {
-            (None, main_part)
+            Some(port_ranges)
         };
+        let host_display = host.to_string();
+        let host = host.to_ascii()?;
+
+        // Everything else — userinfo, query, and fragment — is decoded via `url::Url`. The port
+        // segment may be a port-hopping spec that isn't a valid URL port, so build a probe link
+        // with a dummy port; `host`/`port`/`ports` above are already derived from the real link.
+        let rest_after_main = &link_body[main_part.len()..];
+        let probe_link = format!(
+            "hysteria2://{}{}:1{}",
+            userinfo.map(|u| format!("{}@", u)).unwrap_or_default(),
+            host_display,
+            rest_after_main
+        );
+        let url = Url::parse(&probe_link)
+            .map_err(|e| ProtocolError::UrlParseError(format!("Failed to parse link: {}", e)))?;
 
-        let colon_pos = host_port.find(':').ok_or_else(|| {
-            ProtocolError::InvalidFormat(error_msg::MISSING_COLON_HOST_PORT.to_string())
-        })?;
-
-        let host = &host_port[..colon_pos];
-        let port_str = &host_port[colon_pos + 1..];
-        let port: u16 = port_str.parse().map_err(|e| {
-            ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e))
-        })?;
+        let username = if url.username().is_empty() {
+            None
+        } else {
+            Some(percent_decode(url.username()))
+        };
+        let password = url.password().map(percent_decode);
+        let fragment = url.fragment().map(percent_decode);
 
         // Parse query parameters
         let mut config = Hysteria2Config {
-            host: host.to_string(),
+            host,
             port,
-            password: password.clone(),
+            ports,
+            username,
+            password,
             protocol: None,
-            auth: password,
             alpn: None,
             sni: None,
             insecure: None,
@@ -164,11 +381,9 @@ impl ProtocolParser for Hysteria2 {
             fragment,
         };
 
-        if let Some(query) = query_part {
+        {
             let params: std::collections::HashMap<String, String> =
-                url::form_urlencoded::parse(query.as_bytes())
-                    .into_owned()
-                    .collect();
+                url.query_pairs().into_owned().collect();
 
             // Helper to get parameter with fallback names
             let get_param = |primary: &str, fallbacks: &[&str]| -> Option<String> {
@@ -221,97 +436,113 @@ impl ProtocolParser for Hysteria2 {
             if let Some(hop_str) = params.get("hop_interval") {
                 config.hop_interval = hop_str.parse().ok();
             }
+        }
 
-            // Use password as auth if available
-            if config.auth.is_none() {
-                config.auth = params.get("auth").cloned();
-            }
+        normalize_idna_fields(&mut config)?;
+
+        // Real clients default the TLS SNI to the (ASCII) server host when the link doesn't
+        // specify one explicitly.
+        if config.sni.is_none() {
+            config.sni = Some(config.host.clone());
         }
 
         Ok(Hysteria2 { config })
     }
 
     fn to_link(&self) -> Result<String> {
-        let user_info = if let Some(ref password) = self.config.password {
-            format!("{}@", urlencoding::encode(password))
-        } else {
-            String::new()
+        let user_info = match (&self.config.username, &self.config.password) {
+            (Some(u), Some(p)) => format!(
+                "{}:{}@",
+                urlencoding::encode(u),
+                urlencoding::encode(p)
+            ),
+            (Some(u), None) => format!("{}@", urlencoding::encode(u)),
+            (None, Some(p)) => format!(":{}@", urlencoding::encode(p)),
+            (None, None) => String::new(),
         };
 
-        let mut link = format!(
-            "hysteria2://{}{}:{}",
-            user_info, self.config.host, self.config.port
-        );
+        let host = Host::parse(&self.config.host);
+        let port_spec = match &self.config.ports {
+            Some(ranges) => ranges
+                .iter()
+                .map(|r| r.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+            None => self.config.port.to_string(),
+        };
+        let mut link = format!("hysteria2://{}{}:{}", user_info, host, port_spec);
 
-        // Build query string
-        let mut query_params = Vec::new();
+        // Build query string, encoded via the shared `application/x-www-form-urlencoded` codec
+        // so it matches exactly how `parse` decodes it.
+        let mut pairs: Vec<(&str, String)> = Vec::new();
 
         if let Some(ref protocol) = self.config.protocol
             && protocol != "udp"
         {
-            query_params.push(format!("protocol={}", urlencoding::encode(protocol)));
+            pairs.push(("protocol", protocol.clone()));
         }
 
         if let Some(ref alpn) = self.config.alpn
             && !alpn.is_empty()
         {
-            query_params.push(format!("alpn={}", urlencoding::encode(&alpn.join(","))));
+            pairs.push(("alpn", alpn.join(",")));
         }
 
         if let Some(ref sni) = self.config.sni {
-            query_params.push(format!("sni={}", urlencoding::encode(sni)));
+            pairs.push(("sni", sni.clone()));
         }
 
         if let Some(insecure) = self.config.insecure
             && insecure
         {
-            query_params.push("insecure=1".to_string());
+            pairs.push(("insecure", "1".to_string()));
         }
 
         if let Some(up) = self.config.up_mbps {
-            query_params.push(format!("up_mbps={}", up));
+            pairs.push(("up_mbps", up.to_string()));
         }
 
         if let Some(down) = self.config.down_mbps {
-            query_params.push(format!("down_mbps={}", down));
+            pairs.push(("down_mbps", down.to_string()));
         }
 
         if let Some(recv_conn) = self.config.recv_window_conn
             && recv_conn > 0
         {
-            query_params.push(format!("recv_window_conn={}", recv_conn));
+            pairs.push(("recv_window_conn", recv_conn.to_string()));
         }
 
         if let Some(recv) = self.config.recv_window
             && recv > 0
         {
-            query_params.push(format!("recv_window={}", recv));
+            pairs.push(("recv_window", recv.to_string()));
         }
 
         if let Some(ref obfs) = self.config.obfs {
-            query_params.push(format!("obfs={}", urlencoding::encode(obfs)));
+            pairs.push(("obfs", obfs.clone()));
         }
 
         if let Some(disable) = self.config.disable_mtu_discovery
             && disable
         {
-            query_params.push("disable_mtu_discovery=1".to_string());
+            pairs.push(("disable_mtu_discovery", "1".to_string()));
         }
 
         if let Some(fast_open) = self.config.fast_open
             && fast_open
         {
-            query_params.push("fast_open=1".to_string());
+            pairs.push(("fast_open", "1".to_string()));
         }
 
         if let Some(hop) = self.config.hop_interval
             && hop > 0
         {
-            query_params.push(format!("hop_interval={}", hop));
+            pairs.push(("hop_interval", hop.to_string()));
         }
 
-        if !query_params.is_empty() {
-            link.push_str(&format!("?{}", query_params.join("&")));
+        if !pairs.is_empty() {
+            let query = crate::codec::encode_query(pairs.iter().map(|(k, v)| (*k, v.as_str())));
+            link.push_str(&format!("?{}", query));
         }
 
         // Add fragment if present
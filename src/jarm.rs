@@ -0,0 +1,159 @@
+//! JARM-style TLS fingerprinting for clustering/identifying server configurations.
+//!
+//! A JARM fingerprint is a 62-character fuzzy hash built from 10 TLS "parts", each
+//! conceptually `cipher|version|alpn|extensions`. This module computes the hash from parts
+//! supplied by the caller, so it can be fed either the TLS-relevant fields a parsed link
+//! already carries (`scy`/chosen cipher, `tls`/`security`, `alpn`, `fp`) via
+//! [`parts_from_fields`], or parts gathered from a live handshake (out of scope here: that
+//! requires a TLS client and a network feature flag this crate doesn't have).
+//!
+//! **Hash layout** (62 hex/lowercase chars):
+//! - **First 30 chars**: 3 chars per part (10 parts) — [`cipher_bytes`] (2 chars) followed by
+//!   [`version_byte`] (1 char). A part with no cipher and no version contributes `"000"`.
+//! - **Last 32 chars**: the first 32 hex chars of SHA-256 over the concatenation of every
+//!   part's `extensions` field; if every part's `extensions` is empty, this half is 32 zero
+//!   chars instead of hashing the empty string.
+//!
+//! This is a self-contained reimplementation of the JARM hash format, not a byte-for-byte port
+//! of the reference `jarm.py` (whose cipher/version tables are a larger registry than this
+//! crate needs); [`cipher_bytes`] and [`version_byte`] use fixed, documented tables instead.
+
+use sha2::{Digest, Sha256};
+
+/// Ordered table of cipher suite codes (4 lowercase hex digits) used by [`cipher_bytes`] to
+/// derive a cipher's 2-char byte code from its position in the list.
+const CIPHER_ORDER: &[&str] = &[
+    "1301", "1302", "1303", "1304", "1305", "c02c", "c02b", "c030", "c02f", "c00a", "c009",
+    "c014", "c013", "009d", "009c", "0035", "002f", "000a", "c023", "c024", "c027", "c028",
+];
+
+/// One of the (up to 10) TLS "parts" that make up a JARM fingerprint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JarmPart {
+    /// Chosen cipher suite as a 4-hex-digit code (e.g. `"1301"`), or empty if none was chosen.
+    pub cipher: String,
+    /// Negotiated TLS version as a 4-hex-digit code (e.g. `"0303"` for TLS 1.2), or empty.
+    pub version: String,
+    /// Negotiated ALPN protocol (e.g. `"h2"`), or empty.
+    pub alpn: String,
+    /// Raw extensions string; only this field feeds the hashed second half of the fingerprint.
+    pub extensions: String,
+}
+
+impl std::fmt::Display for JarmPart {
+    /// Formats the part the way JARM conceptually lays it out: `cipher|version|alpn|extensions`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}|{}|{}|{}", self.cipher, self.version, self.alpn, self.extensions)
+    }
+}
+
+/// Supplies cipher-order randomness when synthesizing JARM parts from a single link's static
+/// TLS fields via [`parts_from_fields`]. A real JARM scan sends a distinct cipher order on each
+/// of its 10 probes; reproducing that variation from one link requires a source of randomness,
+/// which production callers back with a real RNG and tests back with a fixed-sequence mock for
+/// a reproducible hash.
+pub trait CipherRng {
+    /// Returns a pseudo-random index in `0..bound`.
+    fn next_index(&mut self, bound: usize) -> usize;
+}
+
+/// A [`CipherRng`] that always returns index `0`; use when cipher-order variation isn't needed
+/// (e.g. a single configured cipher, or a deterministic test fixture).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZeroRng;
+
+impl CipherRng for ZeroRng {
+    fn next_index(&mut self, _bound: usize) -> usize {
+        0
+    }
+}
+
+/// Builds the 10 JARM parts from the TLS-relevant fields a parsed link already carries, cycling
+/// through `ciphers` via `rng` to vary cipher order across parts the way a real JARM scan does
+/// across probes.
+pub fn parts_from_fields(
+    ciphers: &[&str],
+    version: &str,
+    alpn: &str,
+    extensions: &str,
+    rng: &mut impl CipherRng,
+) -> Vec<JarmPart> {
+    (0..10)
+        .map(|_| {
+            let cipher = if ciphers.is_empty() {
+                String::new()
+            } else {
+                ciphers[rng.next_index(ciphers.len())].to_string()
+            };
+            JarmPart {
+                cipher,
+                version: version.to_string(),
+                alpn: alpn.to_string(),
+                extensions: extensions.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Computes a cipher's 2-char byte code: its zero-padded hex index in [`CIPHER_ORDER`], or
+/// `"00"` if `cipher` is empty or not in the table.
+pub fn cipher_bytes(cipher: &str) -> String {
+    if cipher.is_empty() {
+        return "00".to_string();
+    }
+    match CIPHER_ORDER.iter().position(|c| c.eq_ignore_ascii_case(cipher)) {
+        Some(idx) => format!("{:02x}", idx % 256),
+        None => "00".to_string(),
+    }
+}
+
+/// Computes a TLS version's 1-char byte code from a fixed table, or `'0'` if `version` is
+/// empty or unrecognized.
+pub fn version_byte(version: &str) -> char {
+    match version {
+        "0304" => 'd', // TLS 1.3
+        "0303" => 'c', // TLS 1.2
+        "0302" => 'b', // TLS 1.1
+        "0301" => 'a', // TLS 1.0
+        "0300" => '0', // SSL 3.0
+        _ => '0',
+    }
+}
+
+/// Computes JARM fuzzy hashes from caller-supplied parts.
+pub struct Jarm;
+
+impl Jarm {
+    /// Computes the 62-char JARM hash from up to 10 `parts` (missing parts are treated as
+    /// empty, contributing `"000"` to the first half).
+    pub fn hash(parts: &[JarmPart]) -> String {
+        let mut first_half = String::with_capacity(30);
+        let mut extensions_concat = String::new();
+
+        for i in 0..10 {
+            match parts.get(i) {
+                Some(part) => {
+                    if !part.cipher.is_empty() || !part.version.is_empty() {
+                        first_half.push_str(&cipher_bytes(&part.cipher));
+                        first_half.push(version_byte(&part.version));
+                    } else {
+                        first_half.push_str("000");
+                    }
+                    extensions_concat.push_str(&part.extensions);
+                }
+                None => first_half.push_str("000"),
+            }
+        }
+
+        let second_half = if extensions_concat.is_empty() {
+            "0".repeat(32)
+        } else {
+            let mut hasher = Sha256::new();
+            hasher.update(extensions_concat.as_bytes());
+            let digest = hasher.finalize();
+            digest.iter().map(|b| format!("{:02x}", b)).collect::<String>()[..32].to_string()
+        };
+
+        format!("{}{}", first_half, second_half)
+    }
+}
@@ -10,6 +10,14 @@
 //! - Full support for all protocol variants and parameters
 //! - Comprehensive error handling
 //! - Serde support for serialization/deserialization
+//! - [`decode_subscription`]/[`encode_subscription`] for Base64 subscription batches mixing
+//!   multiple protocols, with per-line error reporting instead of all-or-nothing parsing
+//! - [`Jarm`] for JARM-style TLS fuzzy-hashing, to cluster/dedupe servers by their TLS fields
+//! - [`ProtocolRegistry`]/[`Protocol::parse_with`] for registering additional schemes at runtime
+//! - [`Protocol::canonical_key`]/[`Protocol::normalized`] for deduplicating subscription entries
+//!   that differ only by remark or query-parameter order
+//! - [`Protocol::to_multiaddr`]/[`Protocol::from_multiaddr`] for interop with multiaddr/libp2p
+//!   tooling
 //!
 //! ## Supported Protocols
 //!
@@ -65,10 +73,17 @@
 #![warn(missing_docs)]
 #![warn(rustdoc::missing_crate_level_docs)]
 
+mod canonical;
+mod codec;
 mod constants;
 mod error;
+mod host;
 mod hysteria2;
+mod jarm;
+mod multiaddr;
+mod registry;
 mod shadowsocks;
+mod subscription;
 mod trojan;
 mod vless;
 mod vmess;
@@ -77,11 +92,28 @@ mod vmess;
 mod protocols_comprehensive;
 
 pub use error::{ProtocolError, Result};
-pub use hysteria2::{Hysteria2, Hysteria2Config};
-pub use shadowsocks::{Shadowsocks, ShadowsocksConfig};
+pub use host::{Host, HostKind};
+pub use hysteria2::{Hysteria2, Hysteria2Config, PortRange};
+pub use jarm::{cipher_bytes, parts_from_fields, version_byte, CipherRng, Jarm, JarmPart, ZeroRng};
+pub use registry::{DynProtocol, ProtocolRegistry};
+pub use shadowsocks::{CipherKind, PluginConfig, Shadowsocks, ShadowsocksConfig};
+pub use subscription::{decode_subscription, encode_subscription, SubscriptionError};
 pub use trojan::{Trojan, TrojanConfig};
 pub use vless::{VLess, VLessConfig};
-pub use vmess::{VMess, VMessV2};
+pub use vmess::{VMess, VMessFormat, VMessV2};
+
+/// Options controlling how lenient a parser is about malformed input.
+///
+/// The default (lenient) mode matches this crate's historical behavior: a password or fragment
+/// with invalid percent-encoding is passed through best-effort rather than rejected. Set
+/// `strict: true` to instead reject any invalid percent sequence with
+/// [`ProtocolError::UrlParseError`], matching WHATWG URL percent-decoding semantics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseOptions {
+    /// When `true`, invalid percent-encoding in userinfo/fragment is a parse error instead of
+    /// being passed through unchanged.
+    pub strict: bool,
+}
 
 /// Trait for protocol parsers that can parse links and generate links
 pub trait ProtocolParser: Sized {
@@ -143,6 +175,7 @@ pub trait ProtocolParser: Sized {
 ///     Protocol::Shadowsocks(s) => println!("Shadowsocks: {}", s.config.address),
 ///     Protocol::Trojan(t) => println!("Trojan: {}", t.config.address),
 ///     Protocol::Hysteria2(h) => println!("Hysteria2: {}", h.config.host),
+///     Protocol::Custom(c) => println!("Custom ({}): {}", c.scheme(), c.link()?),
 /// }
 ///
 /// // Generate link
@@ -162,6 +195,9 @@ pub enum Protocol {
     Trojan(Trojan),
     /// Hysteria2 protocol
     Hysteria2(Hysteria2),
+    /// A protocol parsed by a scheme registered at runtime via [`Protocol::parse_with`], not one
+    /// of the five built-ins above.
+    Custom(Box<dyn DynProtocol>),
 }
 
 impl Protocol {
@@ -239,6 +275,158 @@ impl Protocol {
             Protocol::Shadowsocks(s) => s.to_link(),
             Protocol::Trojan(t) => t.to_link(),
             Protocol::Hysteria2(h) => h.to_link(),
+            Protocol::Custom(c) => c.link(),
         }
     }
+
+    /// Parses any protocol link using `registry` to resolve its scheme, returning
+    /// `Protocol::Custom` regardless of whether the scheme happens to be one of the five
+    /// built-ins (use [`Protocol::parse`] instead for the built-in-only, always-available path).
+    ///
+    /// This is the extension point for schemes [`Protocol::parse`] doesn't know about: register
+    /// a constructor on a [`ProtocolRegistry`] (or start from [`ProtocolRegistry::default`] for
+    /// the five built-ins already wired up) and pass it here.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::UnsupportedProtocol` if `registry` has no constructor for the
+    /// link's scheme, or whatever error that constructor returns.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vpn_link_serde::{Protocol, ProtocolRegistry};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let registry = ProtocolRegistry::default();
+    /// let protocol = Protocol::parse_with(
+    ///     &registry,
+    ///     "vmess://eyJ2IjoiMiIsImFkZCI6IjEyNy4wLjAuMSIsInBvcnQiOjQ0MywiaWQiOiJ1dWlkLTEyMyJ9",
+    /// )?;
+    /// let link = protocol.to_link()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_with(registry: &ProtocolRegistry, link: &str) -> Result<Self> {
+        Ok(Protocol::Custom(registry.parse(link)?))
+    }
+
+    /// Builds a stable, comparison-only key identifying this endpoint: lowercase/Punycode host,
+    /// sorted query parameters with protocol defaults omitted, and the fragment/remark dropped
+    /// entirely. Two links differing only by remark, query-parameter order, or a default value
+    /// spelled out explicitly produce the same key.
+    ///
+    /// [`Protocol::Custom`] has no structured fields to key on, so it falls back to
+    /// `scheme://to_link()` — still comparison-only, but without the per-field normalization the
+    /// five built-ins get.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vpn_link_serde::Protocol;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let a = Protocol::parse("trojan://pw@Example.COM:443?security=tls&type=tcp#one")?;
+    /// let b = Protocol::parse("trojan://pw@example.com:443?type=tcp&security=tls#two")?;
+    /// assert_eq!(a.canonical_key(), b.canonical_key());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn canonical_key(&self) -> String {
+        match self {
+            Protocol::VMess(v) => v.canonical_key(),
+            Protocol::VLess(v) => v.canonical_key(),
+            Protocol::Shadowsocks(s) => s.canonical_key(),
+            Protocol::Trojan(t) => t.canonical_key(),
+            Protocol::Hysteria2(h) => h.canonical_key(),
+            Protocol::Custom(c) => format!("{}://{}", c.scheme(), c.link().unwrap_or_default()),
+        }
+    }
+
+    /// Returns a cleaned clone: the host-like fields are normalized to ASCII/Punycode and
+    /// lowercased and the remark/tag is cleared, but the result is still a valid [`Protocol`]
+    /// that round-trips through [`Protocol::to_link`]/[`Protocol::parse`] (unlike
+    /// [`Protocol::canonical_key`], which is comparison-only).
+    ///
+    /// [`Protocol::Custom`] can't be introspected generically, so it's returned unchanged.
+    pub fn normalized(&self) -> Self {
+        match self {
+            Protocol::VMess(v) => Protocol::VMess(v.normalized()),
+            Protocol::VLess(v) => Protocol::VLess(v.normalized()),
+            Protocol::Shadowsocks(s) => Protocol::Shadowsocks(s.normalized()),
+            Protocol::Trojan(t) => Protocol::Trojan(t.normalized()),
+            Protocol::Hysteria2(h) => Protocol::Hysteria2(h.normalized()),
+            Protocol::Custom(c) => Protocol::Custom(c.clone()),
+        }
+    }
+
+    /// Encodes this protocol as a multiaddr component chain, e.g.
+    /// `/dns4/example.com/tcp/443/tls/ws/x-scheme/vless/x-id/<uuid>/...`.
+    ///
+    /// Standard-ish components (`ip4`/`ip6`/`dns4`, `tcp`/`udp`, `tls`, `ws`, `h2`) carry the
+    /// transport/security shape; most of the rest of each protocol's fields (including the
+    /// `x-scheme` tag needed to tell VMess/VLess/Trojan apart, since they can share an identical
+    /// transport shape) ride along as `x-<name>`/`<percent-encoded value>` pairs. Remarks/tags
+    /// and `extras` are not encoded, and Hysteria2 carries only its username, password, `sni`,
+    /// and `obfs`.
+    ///
+    /// [`Protocol::Custom`] has no structured fields to encode, so it returns
+    /// `ProtocolError::InvalidField`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if called on `Protocol::Custom`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vpn_link_serde::Protocol;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let protocol = Protocol::parse("trojan://password@example.com:443?security=tls")?;
+    /// let multiaddr = protocol.to_multiaddr()?;
+    /// assert!(multiaddr.starts_with("/dns4/example.com/tcp/443/tls"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_multiaddr(&self) -> Result<String> {
+        match self {
+            Protocol::VMess(v) => Ok(multiaddr::encode_vmess(&v.config)),
+            Protocol::VLess(v) => Ok(multiaddr::encode_vless(&v.config)),
+            Protocol::Shadowsocks(s) => Ok(multiaddr::encode_shadowsocks(&s.config)),
+            Protocol::Trojan(t) => Ok(multiaddr::encode_trojan(&t.config)),
+            Protocol::Hysteria2(h) => Ok(multiaddr::encode_hysteria2(&h.config)),
+            Protocol::Custom(c) => Err(ProtocolError::InvalidField(format!(
+                "Protocol::Custom ({}) has no multiaddr encoding",
+                c.scheme()
+            ))),
+        }
+    }
+
+    /// Decodes a multiaddr produced by [`Protocol::to_multiaddr`] (or built by hand following
+    /// the same grammar) back into the closest matching [`Protocol`] variant, chosen by the
+    /// multiaddr's `x-scheme` component.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if `multiaddr` has no `x-scheme` component, an
+    /// unsupported host/transport component, a malformed port, or is missing a field the chosen
+    /// variant requires (e.g. `x-id` for VMess/VLess, `x-password` for Trojan).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use vpn_link_serde::Protocol;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let protocol = Protocol::parse("trojan://password@example.com:443?security=tls")?;
+    /// let multiaddr = protocol.to_multiaddr()?;
+    /// let round_tripped = Protocol::from_multiaddr(&multiaddr)?;
+    /// assert_eq!(protocol, round_tripped);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_multiaddr(multiaddr: &str) -> Result<Self> {
+        multiaddr::from_multiaddr(multiaddr)
+    }
 }
@@ -0,0 +1,436 @@
+//! Multiaddr import/export, for interop with libp2p-style tooling.
+//!
+//! [`crate::Protocol::to_multiaddr`]/[`crate::Protocol::from_multiaddr`] encode/decode a
+//! [`crate::Protocol`] as a multiaddr component chain, e.g.
+//! `/dns4/example.com/tcp/443/tls/ws/x-scheme/vless/x-id/550e8400-.../x-security/reality`,
+//! modeled on rust-multiaddr's `protocol.rs` table-of-components approach (`from_url` inspired
+//! the host/transport encoding). Standard-ish components (`ip4`/`ip6`/`dns4`/`dns6`,
+//! `tcp`/`udp`, `tls`, `ws`, `h2`) carry the transport/security shape; fields multiaddr has
+//! no component for (the variant tag itself, UUIDs, Reality keys, obfuscation passwords, ...)
+//! ride along as `x-<name>`/`<percent-encoded value>` pairs, following multiaddr's own
+//! convention of an `x-` prefix for protocols outside the registered table. Not every field is
+//! carried: remarks/tags, VMess/VLess `extras`, and the Shadowsocks `legacy_base64` flag are
+//! dropped, and Hysteria2 carries only `username`, `password`, `sni`, and `obfs` (its
+//! port-hopping spec, ALPN, `insecure`, bandwidth, window, and other tuning fields are
+//! dropped). `x-scheme` is the
+//! one opaque pair that's load-bearing rather than best-effort: transport shape alone can't
+//! distinguish VMess from VLess from Trojan, so decoding always trusts it over guessing.
+
+use crate::constants::error_msg;
+use crate::error::{ProtocolError, Result};
+use crate::host::Host;
+use crate::{
+    Hysteria2, Hysteria2Config, PluginConfig, Shadowsocks, ShadowsocksConfig, Trojan, TrojanConfig,
+    VLess, VLessConfig, VMess, VMessV2,
+};
+use std::collections::HashMap;
+
+/// Appends the host and transport/port components: `/{ip4,ip6,dns4}/<host>/{tcp,udp}/<port>`.
+fn push_host_transport(components: &mut Vec<String>, host: &str, port: u16, transport: &str) {
+    match Host::parse(host) {
+        Host::Ipv4(ip) => {
+            components.push("ip4".to_string());
+            components.push(ip.to_string());
+        }
+        Host::Ipv6(ip) => {
+            components.push("ip6".to_string());
+            components.push(ip.to_string());
+        }
+        Host::Domain(d) => {
+            components.push("dns4".to_string());
+            components.push(d);
+        }
+    }
+    components.push(transport.to_string());
+    components.push(port.to_string());
+}
+
+/// Appends an `/x-<name>/<percent-encoded value>` pair if `value` is `Some`.
+fn push_opaque(components: &mut Vec<String>, name: &str, value: Option<&str>) {
+    if let Some(v) = value {
+        components.push(format!("x-{name}"));
+        components.push(urlencoding::encode(v).into_owned());
+    }
+}
+
+/// Joins components with `/`, as a leading-slash multiaddr string.
+fn join(components: Vec<String>) -> String {
+    format!("/{}", components.join("/"))
+}
+
+/// Splits a multiaddr string into its `/`-delimited components (the leading empty segment from
+/// the initial `/` is dropped).
+fn split(multiaddr: &str) -> Vec<&str> {
+    multiaddr.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+/// Sequential cursor over a multiaddr's components, used by each protocol's `decode_*`.
+///
+/// `pub(crate)` (not private) because the `decode_*` functions that take it are themselves
+/// `pub(crate)`, and a private type in a `pub(crate)` fn signature trips `private_interfaces`.
+pub(crate) struct Cursor<'a> {
+    items: &'a [&'a str],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let v = self.items.get(self.pos).copied();
+        if v.is_some() {
+            self.pos += 1;
+        }
+        v
+    }
+}
+
+fn missing(what: &str) -> ProtocolError {
+    ProtocolError::InvalidField(format!("multiaddr is missing {what}"))
+}
+
+/// Reads the leading `/{ip4,ip6,dns4,dns6}/<host>/{tcp,udp}/<port>` components.
+fn pop_host_transport(cursor: &mut Cursor<'_>) -> Result<(String, u16, &'static str)> {
+    let host_proto = cursor.next().ok_or_else(|| missing("a host component"))?;
+    let host = cursor.next().ok_or_else(|| missing("a host value"))?.to_string();
+    if !matches!(host_proto, "ip4" | "ip6" | "dns4" | "dns6") {
+        return Err(ProtocolError::InvalidField(format!(
+            "unsupported multiaddr host component: /{host_proto}"
+        )));
+    }
+    let transport = cursor.next().ok_or_else(|| missing("a transport component"))?;
+    let transport = match transport {
+        "tcp" => "tcp",
+        "udp" => "udp",
+        other => {
+            return Err(ProtocolError::InvalidField(format!(
+                "unsupported multiaddr transport component: /{other}"
+            )))
+        }
+    };
+    let port_str = cursor.next().ok_or_else(|| missing("a port value"))?;
+    let port: u16 = port_str
+        .parse()
+        .map_err(|e| ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e)))?;
+    Ok((host, port, transport))
+}
+
+/// Flags (bare components with no value, e.g. `tls`, `ws`, `h2`) and `x-<name>` opaque pairs
+/// found after the host/transport components.
+struct Tail {
+    flags: std::collections::HashSet<String>,
+    opaque: HashMap<String, String>,
+}
+
+/// Consumes the rest of the cursor into [`Tail`]: an `x-<name>` component always consumes the
+/// next component as its (percent-decoded) value; anything else is a bare flag.
+fn pop_tail(cursor: &mut Cursor<'_>) -> Result<Tail> {
+    let mut flags = std::collections::HashSet::new();
+    let mut opaque = HashMap::new();
+    while let Some(component) = cursor.next() {
+        if let Some(name) = component.strip_prefix("x-") {
+            let value = cursor
+                .next()
+                .ok_or_else(|| missing(&format!("a value for /x-{name}")))?;
+            let decoded = urlencoding::decode(value)
+                .map_err(|e| {
+                    ProtocolError::InvalidField(format!("invalid percent-encoding in /x-{name}: {e}"))
+                })?
+                .into_owned();
+            opaque.insert(name.to_string(), decoded);
+        } else {
+            flags.insert(component.to_string());
+        }
+    }
+    Ok(Tail { flags, opaque })
+}
+
+fn require_opaque(opaque: &HashMap<String, String>, name: &str) -> Result<String> {
+    opaque
+        .get(name)
+        .cloned()
+        .ok_or_else(|| missing(&format!("/x-{name}")))
+}
+
+pub(crate) fn encode_vmess(config: &VMessV2) -> String {
+    let mut c = Vec::new();
+    push_host_transport(&mut c, &config.add, config.port, "tcp");
+    if config.tls.as_deref() == Some("tls") {
+        c.push("tls".to_string());
+    }
+    match config.net.as_deref() {
+        Some("ws") => c.push("ws".to_string()),
+        Some("h2") => c.push("h2".to_string()),
+        other => push_opaque(&mut c, "net", other),
+    }
+    c.push("x-scheme".to_string());
+    c.push("vmess".to_string());
+    push_opaque(&mut c, "id", Some(&config.id));
+    push_opaque(&mut c, "aid", config.aid.map(|v| v.to_string()).as_deref());
+    push_opaque(&mut c, "type", config.r#type.as_deref());
+    push_opaque(&mut c, "host", config.host.as_deref());
+    push_opaque(&mut c, "path", config.path.as_deref());
+    push_opaque(&mut c, "scy", config.scy.as_deref());
+    push_opaque(&mut c, "alpn", config.alpn.as_deref());
+    push_opaque(&mut c, "fp", config.fp.as_deref());
+    push_opaque(&mut c, "sni", config.sni.as_deref());
+    join(c)
+}
+
+pub(crate) fn decode_vmess(cursor: &mut Cursor<'_>) -> Result<VMess> {
+    let (add, port, _transport) = pop_host_transport(cursor)?;
+    let tail = pop_tail(cursor)?;
+    let config = VMessV2 {
+        v: Some("2".to_string()),
+        ps: None,
+        add,
+        port,
+        id: require_opaque(&tail.opaque, "id")?,
+        aid: tail
+            .opaque
+            .get("aid")
+            .map(|v| v.parse::<u16>())
+            .transpose()
+            .map_err(|e| ProtocolError::InvalidField(format!("invalid aid: {e}")))?,
+        net: if tail.flags.contains("ws") {
+            Some("ws".to_string())
+        } else if tail.flags.contains("h2") {
+            Some("h2".to_string())
+        } else {
+            tail.opaque.get("net").cloned()
+        },
+        r#type: tail.opaque.get("type").cloned(),
+        host: tail.opaque.get("host").cloned(),
+        path: tail.opaque.get("path").cloned(),
+        tls: if tail.flags.contains("tls") {
+            Some("tls".to_string())
+        } else {
+            None
+        },
+        scy: tail.opaque.get("scy").cloned(),
+        alpn: tail.opaque.get("alpn").cloned(),
+        fp: tail.opaque.get("fp").cloned(),
+        sni: tail.opaque.get("sni").cloned(),
+        extras: HashMap::new(),
+    };
+    Ok(VMess { config })
+}
+
+pub(crate) fn encode_vless(config: &VLessConfig) -> String {
+    let mut c = Vec::new();
+    push_host_transport(&mut c, &config.address, config.port, "tcp");
+    let is_tls = !matches!(config.security.as_deref(), None | Some("none"));
+    if is_tls {
+        c.push("tls".to_string());
+    }
+    match config.r#type.as_deref() {
+        Some("ws") => c.push("ws".to_string()),
+        Some("h2") => c.push("h2".to_string()),
+        other => push_opaque(&mut c, "type", other),
+    }
+    c.push("x-scheme".to_string());
+    c.push("vless".to_string());
+    push_opaque(&mut c, "id", Some(&config.id));
+    push_opaque(&mut c, "encryption", config.encryption.as_deref());
+    push_opaque(&mut c, "flow", config.flow.as_deref());
+    push_opaque(&mut c, "security", config.security.as_deref());
+    push_opaque(&mut c, "host", config.host.as_deref());
+    push_opaque(&mut c, "path", config.path.as_deref());
+    push_opaque(&mut c, "sni", config.sni.as_deref());
+    push_opaque(&mut c, "fp", config.fp.as_deref());
+    push_opaque(&mut c, "pbk", config.pbk.as_deref());
+    push_opaque(&mut c, "sid", config.sid.as_deref());
+    push_opaque(&mut c, "seed", config.seed.as_deref());
+    push_opaque(&mut c, "headerType", config.header_type.as_deref());
+    join(c)
+}
+
+pub(crate) fn decode_vless(cursor: &mut Cursor<'_>) -> Result<VLess> {
+    let (address, port, _transport) = pop_host_transport(cursor)?;
+    let tail = pop_tail(cursor)?;
+    let r#type = if tail.flags.contains("ws") {
+        Some("ws".to_string())
+    } else if tail.flags.contains("h2") {
+        Some("h2".to_string())
+    } else {
+        tail.opaque.get("type").cloned()
+    };
+    let config = VLessConfig {
+        id: require_opaque(&tail.opaque, "id")?,
+        address,
+        port,
+        encryption: tail.opaque.get("encryption").cloned(),
+        flow: tail.opaque.get("flow").cloned(),
+        security: tail.opaque.get("security").cloned(),
+        r#type,
+        host: tail.opaque.get("host").cloned(),
+        path: tail.opaque.get("path").cloned(),
+        sni: tail.opaque.get("sni").cloned(),
+        fp: tail.opaque.get("fp").cloned(),
+        pbk: tail.opaque.get("pbk").cloned(),
+        sid: tail.opaque.get("sid").cloned(),
+        seed: tail.opaque.get("seed").cloned(),
+        header_type: tail.opaque.get("headerType").cloned(),
+        remark: None,
+        extras: HashMap::new(),
+    };
+    Ok(VLess { config })
+}
+
+pub(crate) fn encode_trojan(config: &TrojanConfig) -> String {
+    let mut c = Vec::new();
+    push_host_transport(&mut c, &config.address, config.port, "tcp");
+    let is_tls = !matches!(config.security.as_deref(), None | Some("none"));
+    if is_tls {
+        c.push("tls".to_string());
+    }
+    match config.r#type.as_deref() {
+        Some("ws") => c.push("ws".to_string()),
+        other => push_opaque(&mut c, "type", other),
+    }
+    c.push("x-scheme".to_string());
+    c.push("trojan".to_string());
+    push_opaque(&mut c, "password", Some(&config.password));
+    push_opaque(&mut c, "flow", config.flow.as_deref());
+    push_opaque(&mut c, "security", config.security.as_deref());
+    push_opaque(&mut c, "sni", config.sni.as_deref());
+    push_opaque(&mut c, "host", config.host.as_deref());
+    push_opaque(&mut c, "fp", config.fp.as_deref());
+    push_opaque(&mut c, "path", config.path.as_deref());
+    join(c)
+}
+
+pub(crate) fn decode_trojan(cursor: &mut Cursor<'_>) -> Result<Trojan> {
+    let (address, port, _transport) = pop_host_transport(cursor)?;
+    let tail = pop_tail(cursor)?;
+    let r#type = if tail.flags.contains("ws") {
+        Some("ws".to_string())
+    } else {
+        tail.opaque.get("type").cloned()
+    };
+    let config = TrojanConfig {
+        password: require_opaque(&tail.opaque, "password")?,
+        address,
+        port,
+        flow: tail.opaque.get("flow").cloned(),
+        security: tail.opaque.get("security").cloned(),
+        sni: tail.opaque.get("sni").cloned(),
+        host: tail.opaque.get("host").cloned(),
+        fp: tail.opaque.get("fp").cloned(),
+        r#type,
+        path: tail.opaque.get("path").cloned(),
+        remark: None,
+    };
+    Ok(Trojan { config })
+}
+
+pub(crate) fn encode_shadowsocks(config: &ShadowsocksConfig) -> String {
+    let mut c = Vec::new();
+    push_host_transport(&mut c, &config.address, config.port, "tcp");
+    c.push("x-scheme".to_string());
+    c.push("ss".to_string());
+    push_opaque(&mut c, "method", Some(&config.method));
+    push_opaque(&mut c, "password", Some(&config.password));
+    if let Some(ref plugin) = config.plugin {
+        push_opaque(&mut c, "plugin", Some(&plugin.to_string()));
+    }
+    join(c)
+}
+
+pub(crate) fn decode_shadowsocks(cursor: &mut Cursor<'_>) -> Result<Shadowsocks> {
+    let (address, port, _transport) = pop_host_transport(cursor)?;
+    let tail = pop_tail(cursor)?;
+    let config = ShadowsocksConfig {
+        method: require_opaque(&tail.opaque, "method")?,
+        password: require_opaque(&tail.opaque, "password")?,
+        address,
+        port,
+        tag: None,
+        plugin: tail.opaque.get("plugin").map(|v| PluginConfig::parse(v)),
+        legacy_base64: false,
+    };
+    Ok(Shadowsocks { config })
+}
+
+pub(crate) fn encode_hysteria2(config: &Hysteria2Config) -> String {
+    let mut c = Vec::new();
+    push_host_transport(&mut c, &config.host, config.port, "udp");
+    c.push("tls".to_string());
+    c.push("x-scheme".to_string());
+    c.push("hysteria2".to_string());
+    push_opaque(&mut c, "username", config.username.as_deref());
+    push_opaque(&mut c, "password", config.password.as_deref());
+    push_opaque(&mut c, "sni", config.sni.as_deref());
+    push_opaque(&mut c, "obfs", config.obfs.as_deref());
+    join(c)
+}
+
+pub(crate) fn decode_hysteria2(cursor: &mut Cursor<'_>) -> Result<Hysteria2> {
+    let (host, port, _transport) = pop_host_transport(cursor)?;
+    let tail = pop_tail(cursor)?;
+    let config = Hysteria2Config {
+        host,
+        port,
+        ports: None,
+        username: tail.opaque.get("username").cloned(),
+        password: tail.opaque.get("password").cloned(),
+        protocol: None,
+        alpn: None,
+        sni: tail.opaque.get("sni").cloned(),
+        insecure: None,
+        up_mbps: None,
+        down_mbps: None,
+        recv_window_conn: None,
+        recv_window: None,
+        obfs: tail.opaque.get("obfs").cloned(),
+        disable_mtu_discovery: None,
+        fast_open: None,
+        hop_interval: None,
+        fragment: None,
+    };
+    Ok(Hysteria2 { config })
+}
+
+/// Scans the full component list for an `x-scheme` pair without consuming the cursor permanently
+/// (a fresh cursor is built from the same slice for the actual per-protocol decode), since the
+/// scheme may appear anywhere after the host/transport components.
+fn find_scheme(items: &[&str]) -> Result<&'static str> {
+    let mut i = 0;
+    while i + 1 < items.len() {
+        if items[i] == "x-scheme" {
+            return match items[i + 1] {
+                "vmess" => Ok("vmess"),
+                "vless" => Ok("vless"),
+                "ss" => Ok("ss"),
+                "trojan" => Ok("trojan"),
+                "hysteria2" => Ok("hysteria2"),
+                other => Err(ProtocolError::InvalidField(format!(
+                    "unknown /x-scheme value: {other}"
+                ))),
+            };
+        }
+        i += 1;
+    }
+    Err(missing("an /x-scheme component"))
+}
+
+/// Parses `multiaddr` into the closest matching [`crate::Protocol`] variant, using its
+/// `x-scheme` component to pick the variant and the rest to fill in that variant's fields (see
+/// the module-level docs for the component grammar).
+///
+/// # Errors
+///
+/// Returns `ProtocolError::InvalidField` if `multiaddr` has no `x-scheme` component, an
+/// unsupported host/transport component, a malformed port, or is missing a field the chosen
+/// variant requires (e.g. `x-id` for VMess/VLess, `x-password` for Trojan).
+pub(crate) fn from_multiaddr(multiaddr: &str) -> Result<crate::Protocol> {
+    let items = split(multiaddr);
+    let scheme = find_scheme(&items)?;
+    let mut cursor = Cursor { items: &items, pos: 0 };
+    match scheme {
+        "vmess" => Ok(crate::Protocol::VMess(decode_vmess(&mut cursor)?)),
+        "vless" => Ok(crate::Protocol::VLess(decode_vless(&mut cursor)?)),
+        "ss" => Ok(crate::Protocol::Shadowsocks(decode_shadowsocks(&mut cursor)?)),
+        "trojan" => Ok(crate::Protocol::Trojan(decode_trojan(&mut cursor)?)),
+        "hysteria2" => Ok(crate::Protocol::Hysteria2(decode_hysteria2(&mut cursor)?)),
+        _ => unreachable!("find_scheme only returns a known scheme"),
+    }
+}
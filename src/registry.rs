@@ -0,0 +1,161 @@
+//! Runtime-extensible protocol registry.
+//!
+//! [`Protocol::parse`](crate::Protocol::parse) only knows the five built-in schemes: adding
+//! support for a new one (e.g. `tuic://`, `juicity://`) means forking the crate. A
+//! [`ProtocolRegistry`] instead maps a lowercase scheme name (no `://`) to a boxed constructor,
+//! so downstream users can register their own parsers and still get unified
+//! [`Protocol::parse_with`](crate::Protocol::parse_with) / [`Protocol::to_link`](crate::Protocol::to_link)
+//! behavior. Modeled on rust-multiaddr's `protocol.rs` table-of-constructors approach.
+//!
+//! [`DynProtocol`] is the object-safe counterpart to [`ProtocolParser`](crate::ProtocolParser):
+//! `ProtocolParser` is `Sized` (it returns `Self` from `parse`), so it can't be used as a trait
+//! object. `DynProtocol` drops that, at the cost of losing `parse` itself (construction is the
+//! registry's job) and structural downcasting (there's no `Any` here, so [`PartialEq`] for boxed
+//! values falls back to comparing [`scheme`](DynProtocol::scheme) and serialized output).
+//!
+//! [`DynProtocol::link`] is deliberately not named `to_link`: every built-in protocol type
+//! implements both this trait and `ProtocolParser`, and a same-named method on both would make
+//! `value.to_link()` ambiguous (`E0034`) at every call site that has both traits in scope.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Hysteria2, ProtocolError, ProtocolParser, Result, Shadowsocks, Trojan, VLess, VMess};
+
+/// Object-safe trait implemented by any protocol type a [`ProtocolRegistry`] can hand back as
+/// `Box<dyn DynProtocol>`.
+pub trait DynProtocol {
+    /// Generate a protocol link string from the structured configuration.
+    ///
+    /// Named `link` rather than `to_link` so it doesn't collide with
+    /// [`ProtocolParser::to_link`](crate::ProtocolParser::to_link), which every built-in
+    /// protocol type also implements.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if the configuration cannot be serialized.
+    fn link(&self) -> Result<String>;
+
+    /// Lowercase scheme name (no `://`) this value was constructed for, e.g. `"vmess"`.
+    ///
+    /// Serves as a type tag in place of downcasting, since `Box<dyn DynProtocol>` can't be
+    /// downcast to a concrete type without `Any`.
+    fn scheme(&self) -> &'static str;
+
+    /// Clones this boxed value.
+    ///
+    /// Required because `Box<dyn DynProtocol>` can't derive `Clone`; backs the manual
+    /// `Clone for Box<dyn DynProtocol>` impl below.
+    fn clone_box(&self) -> Box<dyn DynProtocol>;
+}
+
+impl fmt::Debug for dyn DynProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DynProtocol")
+            .field("scheme", &self.scheme())
+            .finish()
+    }
+}
+
+impl Clone for Box<dyn DynProtocol> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl PartialEq for Box<dyn DynProtocol> {
+    /// Best-effort equality: same scheme and same serialized link. The closest structural proxy
+    /// available without `Any`-based downcasting.
+    fn eq(&self, other: &Self) -> bool {
+        self.scheme() == other.scheme() && self.link().ok() == other.link().ok()
+    }
+}
+
+macro_rules! impl_dyn_protocol {
+    ($ty:ty, $scheme:expr) => {
+        impl DynProtocol for $ty {
+            fn link(&self) -> Result<String> {
+                ProtocolParser::to_link(self)
+            }
+
+            fn scheme(&self) -> &'static str {
+                $scheme
+            }
+
+            fn clone_box(&self) -> Box<dyn DynProtocol> {
+                Box::new(self.clone())
+            }
+        }
+    };
+}
+
+impl_dyn_protocol!(VMess, "vmess");
+impl_dyn_protocol!(VLess, "vless");
+impl_dyn_protocol!(Shadowsocks, "ss");
+impl_dyn_protocol!(Trojan, "trojan");
+impl_dyn_protocol!(Hysteria2, "hysteria2");
+
+/// A table mapping lowercase scheme names (no `://`) to boxed constructors, used by
+/// [`Protocol::parse_with`](crate::Protocol::parse_with) to parse schemes it doesn't know about
+/// natively.
+pub struct ProtocolRegistry {
+    constructors: HashMap<String, Box<dyn Fn(&str) -> Result<Box<dyn DynProtocol>>>>,
+}
+
+impl fmt::Debug for ProtocolRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtocolRegistry")
+            .field("schemes", &self.constructors.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl ProtocolRegistry {
+    /// Creates an empty registry with no schemes registered.
+    pub fn new() -> Self {
+        Self {
+            constructors: HashMap::new(),
+        }
+    }
+
+    /// Registers a constructor for `scheme` (matched case-insensitively, without `://`),
+    /// replacing any constructor previously registered for it.
+    pub fn register<F>(&mut self, scheme: &str, constructor: F)
+    where
+        F: Fn(&str) -> Result<Box<dyn DynProtocol>> + 'static,
+    {
+        self.constructors
+            .insert(scheme.to_lowercase(), Box::new(constructor));
+    }
+
+    /// Parses `link` using the constructor registered for its scheme.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::UnsupportedProtocol` if no scheme in `link` (the part before
+    /// `://`) has a registered constructor, or whatever error the constructor itself returns.
+    pub fn parse(&self, link: &str) -> Result<Box<dyn DynProtocol>> {
+        let scheme = link.split("://").next().unwrap_or("").to_lowercase();
+        match self.constructors.get(&scheme) {
+            Some(constructor) => constructor(link),
+            None => Err(ProtocolError::UnsupportedProtocol(format!(
+                "Unsupported protocol: {}",
+                scheme
+            ))),
+        }
+    }
+}
+
+impl Default for ProtocolRegistry {
+    /// A registry preloaded with the five built-in schemes (`vmess`, `vless`, `ss`, `trojan`,
+    /// `hysteria2`).
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register("vmess", |link| Ok(Box::new(VMess::parse(link)?)));
+        registry.register("vless", |link| Ok(Box::new(VLess::parse(link)?)));
+        registry.register("ss", |link| Ok(Box::new(Shadowsocks::parse(link)?)));
+        registry.register("trojan", |link| Ok(Box::new(Trojan::parse(link)?)));
+        registry.register("hysteria2", |link| Ok(Box::new(Hysteria2::parse(link)?)));
+        registry
+    }
+}
@@ -13,12 +13,113 @@
 //! 1. Prefix `ss://` is case-insensitive.
 //! 2. Fragment (tag) is split by `#`; query (plugin) by `?`. Remainder is `[userinfo@]hostname:port`.
 //! 3. If the part before `@` (or the whole body if no `@`) is valid Base64, decode to get `method:password`; hostname and port come from the part after `@` or the whole body. Port must parse as u16.
+//! 4. An IPv6 literal host is written bracketed (`[::1]:8388`); brackets are stripped from `address`
+//!    on parse (via the shared [`crate::host::split_host_port`]) and validated as a
+//!    `std::net::Ipv6Addr`, then re-added by `to_link`.
+//! 5. **Legacy fully-Base64 form**: the whole remainder (no literal `@`) decodes as a single Base64
+//!    blob of `method:password@host:port`. The standard alphabet with `=` padding is tried first,
+//!    then the URL-safe alphabet without padding. `ShadowsocksConfig::legacy_base64` records this so `to_link` can
+//!    round-trip the original layout instead of always emitting SIP002.
+//! 6. `address` is normalized to ASCII (Punycode) via IDNA on parse; the original Unicode form
+//!    is available via [`Shadowsocks::address_unicode`], and [`Shadowsocks::to_link_idna`]
+//!    guarantees ASCII-only output even for a manually constructed configuration.
+//! 7. `to_link` serializes the `plugin` query parameter with the shared
+//!    `application/x-www-form-urlencoded` codec (`crate::codec::encode_query`).
+//! 8. [`Shadowsocks::parse_strict`] additionally calls [`Shadowsocks::validate`], which checks
+//!    `port` isn't 0 and classifies `address` with [`Shadowsocks::address_kind`], rejecting a
+//!    malformed authority (empty host, an empty label, a label over 63 bytes, or an
+//!    invalid-looking IPv4 literal such as `1.2.3.999`).
 
 use crate::ProtocolParser;
+use crate::canonical;
 use crate::constants::{error_msg, scheme};
 use crate::error::{ProtocolError, Result};
+use crate::host::{self, Host, HostKind};
 use base64::Engine;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::Ipv6Addr;
+
+/// Splits a `host:port[/]` segment, treating a leading `[...]` as a bracketed IPv6 literal.
+///
+/// Delegates the actual bracket/IP/domain discipline to the shared, WHATWG-host-inspired
+/// [`host::split_host_port`], then collapses its `Host` back to a plain (unbracketed) string so
+/// existing callers keep working with `address: String`. A trailing `/` on the port (some
+/// clients append one after the authority) is trimmed, as before.
+fn split_host_port(host_port: &str) -> Result<(String, &str)> {
+    let (parsed_host, port_str) = host::split_host_port(host_port)?;
+    let address = match parsed_host {
+        Host::Ipv4(ip) => ip.to_string(),
+        Host::Ipv6(ip) => ip.to_string(),
+        Host::Domain(d) => d,
+    };
+    Ok((address, port_str.trim_end_matches('/')))
+}
+
+/// SIP003 plugin specification: `plugin-name[;opt=value;opt2=value2...]`.
+///
+/// Serializes/deserializes as the raw `name;opts` string so existing JSON consumers that treat
+/// `plugin` as a plain string keep working; use [`PluginConfig::name`] and [`PluginConfig::opts`]
+/// instead of re-splitting the string yourself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginConfig {
+    /// Plugin executable name (e.g. `obfs-local`, `v2ray-plugin`).
+    pub name: String,
+    /// Raw, semicolon-delimited plugin options (e.g. `obfs=tls;obfs-host=example.com`).
+    pub opts: Option<String>,
+}
+
+impl PluginConfig {
+    /// Parses a SIP003 `plugin` value, splitting the name from its options on the first `;`.
+    pub fn parse(value: &str) -> Self {
+        match value.split_once(';') {
+            Some((name, opts)) => PluginConfig {
+                name: name.to_string(),
+                opts: Some(opts.to_string()),
+            },
+            None => PluginConfig {
+                name: value.to_string(),
+                opts: None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for PluginConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.opts {
+            Some(opts) => write!(f, "{};{}", self.name, opts),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl Serialize for PluginConfig {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for PluginConfig {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        Ok(PluginConfig::parse(&s))
+    }
+}
+
+/// Decodes `data` as Base64, trying the standard alphabet (with padding) first, then the
+/// URL-safe alphabet without padding.
+fn decode_base64_any(data: &str) -> std::result::Result<Vec<u8>, base64::DecodeError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .or_else(|_| base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(data))
+}
 
 /// Shadowsocks configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -34,9 +135,14 @@ pub struct ShadowsocksConfig {
     /// Tag/remark
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tag: Option<String>,
-    /// Plugin information
+    /// SIP003 plugin (name and options)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub plugin: Option<String>,
+    pub plugin: Option<PluginConfig>,
+    /// Whether this config was parsed from (and should serialize back to) the legacy
+    /// fully-Base64-encoded layout (`ss://base64(method:password@host:port)#tag`) rather
+    /// than SIP002.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub legacy_base64: bool,
 }
 
 /// Shadowsocks protocol parser
@@ -46,6 +152,280 @@ pub struct Shadowsocks {
     pub config: ShadowsocksConfig,
 }
 
+/// Validated Shadowsocks encryption methods, mirroring the method table in shadowsocks-rust's
+/// config (stream ciphers, AEAD ciphers, and the newer AEAD-2022 methods).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CipherKind {
+    /// `rc4-md5`
+    Rc4Md5,
+    /// `aes-128-cfb`
+    Aes128Cfb,
+    /// `aes-192-cfb`
+    Aes192Cfb,
+    /// `aes-256-cfb`
+    Aes256Cfb,
+    /// `aes-128-ctr`
+    Aes128Ctr,
+    /// `aes-192-ctr`
+    Aes192Ctr,
+    /// `aes-256-ctr`
+    Aes256Ctr,
+    /// `camellia-128-cfb`
+    Camellia128Cfb,
+    /// `camellia-192-cfb`
+    Camellia192Cfb,
+    /// `camellia-256-cfb`
+    Camellia256Cfb,
+    /// `chacha20-ietf`
+    Chacha20Ietf,
+    /// `aes-128-gcm`
+    Aes128Gcm,
+    /// `aes-256-gcm`
+    Aes256Gcm,
+    /// `chacha20-ietf-poly1305` (alias `chacha20-poly1305`)
+    Chacha20IetfPoly1305,
+    /// `xchacha20-ietf-poly1305`
+    XChacha20IetfPoly1305,
+    /// `2022-blake3-aes-128-gcm` (AEAD-2022, 16-byte PSK)
+    Aead2022Blake3Aes128Gcm,
+    /// `2022-blake3-aes-256-gcm` (AEAD-2022, 32-byte PSK)
+    Aead2022Blake3Aes256Gcm,
+    /// `2022-blake3-chacha20-poly1305` (AEAD-2022, 32-byte PSK)
+    Aead2022Blake3ChaCha20Poly1305,
+}
+
+impl CipherKind {
+    /// Parses a method name as used in `ss://` links and shadowsocks-rust configs.
+    pub fn parse(method: &str) -> Option<Self> {
+        Some(match method {
+            "rc4-md5" => CipherKind::Rc4Md5,
+            "aes-128-cfb" => CipherKind::Aes128Cfb,
+            "aes-192-cfb" => CipherKind::Aes192Cfb,
+            "aes-256-cfb" => CipherKind::Aes256Cfb,
+            "aes-128-ctr" => CipherKind::Aes128Ctr,
+            "aes-192-ctr" => CipherKind::Aes192Ctr,
+            "aes-256-ctr" => CipherKind::Aes256Ctr,
+            "camellia-128-cfb" => CipherKind::Camellia128Cfb,
+            "camellia-192-cfb" => CipherKind::Camellia192Cfb,
+            "camellia-256-cfb" => CipherKind::Camellia256Cfb,
+            "chacha20-ietf" => CipherKind::Chacha20Ietf,
+            "aes-128-gcm" => CipherKind::Aes128Gcm,
+            "aes-256-gcm" => CipherKind::Aes256Gcm,
+            "chacha20-ietf-poly1305" | "chacha20-poly1305" => CipherKind::Chacha20IetfPoly1305,
+            "xchacha20-ietf-poly1305" => CipherKind::XChacha20IetfPoly1305,
+            "2022-blake3-aes-128-gcm" => CipherKind::Aead2022Blake3Aes128Gcm,
+            "2022-blake3-aes-256-gcm" => CipherKind::Aead2022Blake3Aes256Gcm,
+            "2022-blake3-chacha20-poly1305" => CipherKind::Aead2022Blake3ChaCha20Poly1305,
+            _ => return None,
+        })
+    }
+
+    /// Returns true for the AEAD-2022 method family, which requires a fixed-size Base64 PSK
+    /// rather than an arbitrary passphrase.
+    pub fn is_aead_2022(&self) -> bool {
+        matches!(
+            self,
+            CipherKind::Aead2022Blake3Aes128Gcm
+                | CipherKind::Aead2022Blake3Aes256Gcm
+                | CipherKind::Aead2022Blake3ChaCha20Poly1305
+        )
+    }
+
+    /// Returns the required pre-shared-key length in bytes for AEAD-2022 methods, or `None`
+    /// for methods that take an arbitrary passphrase.
+    pub fn psk_len(&self) -> Option<usize> {
+        match self {
+            CipherKind::Aead2022Blake3Aes128Gcm => Some(16),
+            CipherKind::Aead2022Blake3Aes256Gcm | CipherKind::Aead2022Blake3ChaCha20Poly1305 => {
+                Some(32)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Validates `method` against [`CipherKind`] and, for AEAD-2022 methods, that `password` is
+/// Base64 of the expected key length.
+fn validate_method(method: &str, password: &str) -> Result<()> {
+    let cipher = CipherKind::parse(method)
+        .ok_or_else(|| ProtocolError::InvalidField(format!("Unknown encryption method: {}", method)))?;
+
+    if let Some(expected_len) = cipher.psk_len() {
+        let key = decode_base64_any(password).map_err(|_| {
+            ProtocolError::InvalidField(format!(
+                "AEAD-2022 method {} requires a Base64-encoded PSK",
+                method
+            ))
+        })?;
+        if key.len() != expected_len {
+            return Err(ProtocolError::InvalidField(format!(
+                "AEAD-2022 method {} requires a {}-byte PSK, got {}",
+                method,
+                expected_len,
+                key.len()
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// On-disk JSON shape used by the shadowsocks-rust daemon (`ss-server`/`sslocal` config files).
+///
+/// Field names follow shadowsocks-rust's own config struct rather than this crate's link-centric
+/// naming, so `from_json_config`/`to_json_config` can round-trip unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SsJsonConfig {
+    server: String,
+    server_port: u16,
+    password: String,
+    method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plugin: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plugin_opts: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeout: Option<u64>,
+}
+
+impl Shadowsocks {
+    /// Parses a shadowsocks-rust server JSON config (`server`, `server_port`, `password`,
+    /// `method`, `plugin`, `plugin_opts`, `timeout`) into a [`Shadowsocks`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::JsonParseError`] if `json` is not a valid config object.
+    pub fn from_json_config(json: &str) -> Result<Self> {
+        let raw: SsJsonConfig = serde_json::from_str(json)?;
+
+        let plugin = raw.plugin.map(|name| PluginConfig {
+            name,
+            opts: raw.plugin_opts,
+        });
+
+        Ok(Shadowsocks {
+            config: ShadowsocksConfig {
+                method: raw.method,
+                password: raw.password,
+                address: raw.server,
+                port: raw.server_port,
+                tag: None,
+                plugin,
+                legacy_base64: false,
+            },
+        })
+    }
+
+    /// Serializes this config as a shadowsocks-rust server JSON config.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtocolError::JsonParseError`] if serialization fails.
+    pub fn to_json_config(&self) -> Result<String> {
+        let (plugin, plugin_opts) = match &self.config.plugin {
+            Some(p) => (Some(p.name.clone()), p.opts.clone()),
+            None => (None, None),
+        };
+
+        let raw = SsJsonConfig {
+            server: self.config.address.clone(),
+            server_port: self.config.port,
+            password: self.config.password.clone(),
+            method: self.config.method.clone(),
+            plugin,
+            plugin_opts,
+            timeout: None,
+        };
+
+        Ok(serde_json::to_string(&raw)?)
+    }
+
+    /// Returns `address` in its Unicode display form (reversing IDNA Punycode), unchanged if it
+    /// has no Punycode labels.
+    pub fn address_unicode(&self) -> String {
+        Host::parse(&self.config.address).to_unicode()
+    }
+
+    /// Generates a link like [`ProtocolParser::to_link`], but first normalizes `address` to
+    /// ASCII so the output is guaranteed ASCII-only even if the configuration was built directly
+    /// (rather than via `parse`, which already normalizes this field).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if `address` isn't a valid IDNA host, or other
+    /// `ProtocolError` variants if the configuration cannot be serialized.
+    pub fn to_link_idna(&self) -> Result<String> {
+        let mut config = self.config.clone();
+        config.address = Host::parse(&config.address).to_ascii()?;
+        Shadowsocks { config }.to_link()
+    }
+
+    /// Classifies `address` as an IPv4 literal, IPv6 literal, or domain name, rejecting a
+    /// malformed authority (see [`crate::host::validate_host`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if `address` is neither a valid IP literal nor a
+    /// valid domain name.
+    pub fn address_kind(&self) -> Result<HostKind> {
+        host::validate_host(&self.config.address)
+    }
+
+    /// Parses a Shadowsocks link and additionally checks it with [`Shadowsocks::validate`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if the link format is invalid or unsupported, or
+    /// `ProtocolError::InvalidField` if it fails semantic validation.
+    pub fn parse_strict(link: &str) -> Result<Self> {
+        let ss = Self::parse(link)?;
+        ss.validate()?;
+        Ok(ss)
+    }
+
+    /// Checks semantic correctness beyond what `parse` enforces structurally: `port` isn't 0
+    /// and `address` classifies as a valid IP literal or domain name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` describing the first check that fails.
+    pub fn validate(&self) -> Result<()> {
+        if self.config.port == 0 {
+            return Err(ProtocolError::InvalidField(
+                "port must be in 1..=65535, got 0".to_string(),
+            ));
+        }
+        self.address_kind()?;
+        Ok(())
+    }
+
+    /// Builds a stable, comparison-only key: see [`crate::Protocol::canonical_key`]. `method`
+    /// and `password` are included since they select a different credential on the same server;
+    /// `tag` and `legacy_base64` (purely a serialization choice) are dropped.
+    pub fn canonical_key(&self) -> String {
+        let address = canonical::normalize_host(&self.config.address);
+        let mut params: Vec<(&str, String)> = vec![
+            ("method", self.config.method.clone()),
+            ("password", self.config.password.clone()),
+        ];
+        if let Some(ref plugin) = self.config.plugin {
+            params.push(("plugin", plugin.to_string()));
+        }
+        canonical::build_key("ss", &address, self.config.port, params)
+    }
+
+    /// Returns a cleaned clone: `address` normalized to ASCII/Punycode and lowercased, `tag`
+    /// cleared. Unlike [`Shadowsocks::canonical_key`] the result is still a valid, parseable
+    /// [`Shadowsocks`].
+    pub fn normalized(&self) -> Self {
+        let mut config = self.config.clone();
+        config.address = canonical::normalize_host(&config.address);
+        config.tag = None;
+        Shadowsocks { config }
+    }
+}
+
 impl ProtocolParser for Shadowsocks {
     fn parse(link: &str) -> Result<Self> {
         if !link.to_lowercase().starts_with(scheme::SHADOWSOCKS) {
@@ -79,27 +459,32 @@ impl ProtocolParser for Shadowsocks {
                     url::form_urlencoded::parse(query_str.as_bytes())
                         .into_owned()
                         .collect();
-                let plugin = params.get("plugin").cloned();
+                let plugin = params.get("plugin").map(|p| PluginConfig::parse(p));
                 (&main_part[..query_pos], plugin)
             } else {
                 (main_part, None)
             }
         };
 
-        // Check if it's base64 encoded or SIP002 format
-        let (method, password, address, port) = if address_part
-            .chars()
-            .all(|c| c.is_alphanumeric() || c == '+' || c == '/' || c == '=')
-        {
-            // Base64 encoded format: base64(method:password)@host:port
-            let decoded = base64::engine::general_purpose::STANDARD.decode(address_part)?;
-            let decoded_str = String::from_utf8(decoded)
-                .map_err(|e| ProtocolError::InvalidFormat(format!("Invalid UTF-8: {}", e)))?;
-
-            let at_pos = decoded_str
-                .rfind('@')
-                .ok_or_else(|| ProtocolError::InvalidFormat(error_msg::MISSING_AT.to_string()))?;
+        // Try the legacy fully-Base64 layout first: the whole body (no literal '@') decodes to
+        // `method:password@host:port`. Fall back to SIP002 if whole-body decoding fails or the
+        // decoded string doesn't look like `method:password@host:port`.
+        let legacy_decoded = decode_base64_any(address_part)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .filter(|decoded_str| {
+                decoded_str
+                    .rfind('@')
+                    .map(|at_pos| {
+                        decoded_str[..at_pos].contains(':') && decoded_str[at_pos + 1..].contains(':')
+                    })
+                    .unwrap_or(false)
+            });
 
+        let (method, password, address, port, legacy_base64) = if let Some(decoded_str) =
+            legacy_decoded
+        {
+            let at_pos = decoded_str.rfind('@').expect("checked by filter above");
             let method_password = &decoded_str[..at_pos];
             let host_port = &decoded_str[at_pos + 1..];
 
@@ -110,12 +495,7 @@ impl ProtocolParser for Shadowsocks {
             let method = &method_password[..colon_pos];
             let password = &method_password[colon_pos + 1..];
 
-            let hp_colon = host_port.find(':').ok_or_else(|| {
-                ProtocolError::InvalidFormat(error_msg::MISSING_COLON_HOST_PORT.to_string())
-            })?;
-
-            let address = &host_port[..hp_colon];
-            let port_str = host_port[hp_colon + 1..].trim_end_matches('/');
+            let (address, port_str) = split_host_port(host_port)?;
             let port: u16 = port_str.parse().map_err(|e| {
                 ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e))
             })?;
@@ -123,8 +503,9 @@ impl ProtocolParser for Shadowsocks {
             (
                 method.to_string(),
                 password.to_string(),
-                address.to_string(),
+                address,
                 port,
+                true,
             )
         } else {
             // SIP002 format: method:password@host:port (URL encoded)
@@ -146,12 +527,7 @@ impl ProtocolParser for Shadowsocks {
             let method = &user_str[..colon_pos];
             let password = &user_str[colon_pos + 1..];
 
-            let hp_colon = host_port.find(':').ok_or_else(|| {
-                ProtocolError::InvalidFormat(error_msg::MISSING_COLON_HOST_PORT.to_string())
-            })?;
-
-            let address = &host_port[..hp_colon];
-            let port_str = &host_port[hp_colon + 1..];
+            let (address, port_str) = split_host_port(host_port)?;
             let port: u16 = port_str.parse().map_err(|e| {
                 ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e))
             })?;
@@ -159,11 +535,15 @@ impl ProtocolParser for Shadowsocks {
             (
                 method.to_string(),
                 password.to_string(),
-                address.to_string(),
+                address,
                 port,
+                false,
             )
         };
 
+        validate_method(&method, &password)?;
+        let address = Host::parse(&address).to_ascii()?;
+
         Ok(Shadowsocks {
             config: ShadowsocksConfig {
                 method,
@@ -172,27 +552,47 @@ impl ProtocolParser for Shadowsocks {
                 port,
                 tag,
                 plugin,
+                legacy_base64,
             },
         })
     }
 
     fn to_link(&self) -> Result<String> {
+        let address = if self.config.address.parse::<Ipv6Addr>().is_ok() {
+            format!("[{}]", self.config.address)
+        } else {
+            self.config.address.clone()
+        };
+
+        if self.config.legacy_base64 {
+            // Legacy layout: ss://base64(method:password@host:port)#tag
+            let body = format!(
+                "{}:{}@{}:{}",
+                self.config.method, self.config.password, address, self.config.port
+            );
+            let encoded = base64::engine::general_purpose::STANDARD.encode(body.as_bytes());
+            let mut link = format!("ss://{}", encoded);
+            if let Some(ref tag) = self.config.tag {
+                link.push_str(&format!("#{}", urlencoding::encode(tag)));
+            }
+            return Ok(link);
+        }
+
         // Use SIP002 format: ss://base64(method:password)@host:port
         let user_info = format!("{}:{}", self.config.method, self.config.password);
         let encoded_user = base64::engine::general_purpose::STANDARD.encode(user_info.as_bytes());
 
         // SIP002: port 后应有 / 再接 ?plugin
-        let mut link = format!(
-            "ss://{}@{}:{}",
-            encoded_user, self.config.address, self.config.port
-        );
+        let mut link = format!("ss://{}@{}:{}", encoded_user, address, self.config.port);
         if self.config.plugin.is_some() {
             link.push('/');
         }
 
-        // Add plugin query parameter if present
+        // Add plugin query parameter if present, encoded via the shared
+        // `application/x-www-form-urlencoded` codec so it matches how it's decoded.
         if let Some(ref plugin) = self.config.plugin {
-            link.push_str(&format!("?plugin={}", urlencoding::encode(plugin)));
+            link.push('?');
+            link.push_str(&crate::codec::encode_query([("plugin", plugin.to_string().as_str())]));
         }
 
         // Add tag (fragment) if present
@@ -0,0 +1,104 @@
+//! Subscription decoder subsystem for batch link parsing.
+//!
+//! A subscription endpoint typically returns its whole server list as a single Base64 blob:
+//! decoding it yields a newline-delimited list of links mixing schemes (`vmess://`,
+//! `hysteria2://`, `ss://`, ...). [`decode_subscription`] decodes and dispatches each line via
+//! [`Protocol::parse`], collecting per-line failures instead of failing the whole batch.
+//! [`encode_subscription`] is the inverse: it serializes a list of [`Protocol`]s back to
+//! subscription form via each one's `to_link`.
+//!
+//! ## Decoding rules
+//!
+//! 1. The outer content is decoded as Base64, trying the standard and URL-safe alphabets, each
+//!    with and without `=` padding, in that order; the first that decodes wins, and its bytes
+//!    must be valid UTF-8 (otherwise decoding fails without trying the remaining alphabets).
+//! 2. The decoded text is split on `\n` (a trailing `\r` is trimmed); blank lines are skipped.
+//! 3. Each remaining line is parsed with [`Protocol::parse`]; failures are recorded as a
+//!    [`SubscriptionError`] (1-based line number, the original line text, and the error) rather
+//!    than aborting the batch.
+//!
+//! ## Encoding rules
+//!
+//! [`encode_subscription`] joins each protocol's `to_link()` output with `\n` and encodes the
+//! result with the standard Base64 alphabet, padded — the most widely accepted subscription
+//! form.
+
+use crate::error::{ProtocolError, Result};
+use crate::{Protocol, ProtocolParser};
+use base64::Engine;
+
+/// A single line from a subscription batch that failed to parse.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionError {
+    /// 1-based line number within the decoded subscription content.
+    pub line: usize,
+    /// The original (undecoded) line text.
+    pub text: String,
+    /// The parse error for this line.
+    pub error: ProtocolError,
+}
+
+/// Decodes subscription content and parses every line, returning the successfully parsed
+/// protocols alongside the per-line errors for the rest.
+///
+/// # Errors
+///
+/// Returns `ProtocolError::Base64DecodeError` if the content doesn't decode as Base64 under any
+/// of the supported alphabet/padding combinations, or `ProtocolError::UrlParseError` if the
+/// decoded bytes aren't valid UTF-8.
+pub fn decode_subscription(content: &str) -> Result<(Vec<Protocol>, Vec<SubscriptionError>)> {
+    let decoded = decode_base64_any(content.trim())?;
+
+    let mut protocols = Vec::new();
+    let mut errors = Vec::new();
+
+    for (i, raw_line) in decoded.split('\n').enumerate() {
+        let line = raw_line.trim_end_matches('\r').trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match Protocol::parse(line) {
+            Ok(protocol) => protocols.push(protocol),
+            Err(error) => errors.push(SubscriptionError {
+                line: i + 1,
+                text: line.to_string(),
+                error,
+            }),
+        }
+    }
+
+    Ok((protocols, errors))
+}
+
+/// Serializes protocols back into Base64-encoded, newline-delimited subscription content.
+///
+/// # Errors
+///
+/// Returns `ProtocolError` if any protocol's `to_link` fails.
+pub fn encode_subscription(protocols: &[Protocol]) -> Result<String> {
+    let links = protocols
+        .iter()
+        .map(Protocol::to_link)
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(base64::engine::general_purpose::STANDARD.encode(links.join("\n")))
+}
+
+/// Decodes Base64 text to a UTF-8 string, trying the standard and URL-safe alphabets, each with
+/// and without `=` padding, so this crate tolerates whichever flavor a subscription host emits.
+fn decode_base64_any(data: &str) -> Result<String> {
+    use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+
+    STANDARD
+        .decode(data)
+        .or_else(|_| STANDARD_NO_PAD.decode(data))
+        .or_else(|_| URL_SAFE.decode(data))
+        .or_else(|_| URL_SAFE_NO_PAD.decode(data))
+        .map_err(ProtocolError::from)
+        .and_then(|bytes| {
+            String::from_utf8(bytes).map_err(|e| {
+                ProtocolError::UrlParseError(format!("Subscription content isn't valid UTF-8: {}", e))
+            })
+        })
+}
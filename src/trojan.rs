@@ -14,11 +14,32 @@
 //! 2. Main part must contain `@` and `:` (`password@address:port`); otherwise `InvalidFormat`.
 //! 3. Port must parse as u16; otherwise `InvalidField`.
 //! 4. Query and fragment are parsed as above.
+//! 5. An IPv6 literal host is written bracketed (`[::1]:443`); the brackets are stripped from
+//!    `address` on parse (via the shared [`crate::host::split_host_port`]) and the content is
+//!    validated as a `std::net::Ipv6Addr`, then re-added by `to_link` when `address` parses as
+//!    IPv6.
+//! 6. [`Trojan::parse_with_options`] accepts a [`ParseOptions`] to opt into strict percent-decoding
+//!    of the password and fragment (see crate-level docs); [`ProtocolParser::parse`] uses the
+//!    lenient default.
+//! 7. `address`, `host`, and `sni` are normalized to ASCII (Punycode) via IDNA on parse; the
+//!    original Unicode form is available via [`Trojan::address_unicode`]/[`Trojan::host_unicode`]/
+//!    [`Trojan::sni_unicode`], and [`Trojan::to_link_idna`] guarantees ASCII-only output even for
+//!    a manually constructed configuration.
+//! 8. `to_link` serializes the query string with the shared `application/x-www-form-urlencoded`
+//!    codec (`crate::codec::encode_query`), the exact inverse of how it's decoded.
+//! 9. [`Trojan::parse_strict`] additionally calls [`Trojan::validate`], which checks `port`
+//!    isn't 0 and classifies `address` with [`Trojan::address_kind`], rejecting a malformed
+//!    authority (empty host, an empty label, a label over 63 bytes, or an invalid-looking
+//!    IPv4 literal such as `1.2.3.999`).
 
+use crate::ParseOptions;
 use crate::ProtocolParser;
+use crate::canonical;
 use crate::constants::{error_msg, scheme};
 use crate::error::{ProtocolError, Result};
+use crate::host::{self, Host, HostKind};
 use serde::{Deserialize, Serialize};
+use std::net::Ipv6Addr;
 
 /// Trojan configuration structure
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -62,8 +83,31 @@ pub struct Trojan {
     pub config: TrojanConfig,
 }
 
-impl ProtocolParser for Trojan {
-    fn parse(link: &str) -> Result<Self> {
+/// Splits a `host:port` segment, treating a leading `[...]` as a bracketed IPv6 literal.
+///
+/// Delegates the actual bracket/IP/domain discipline to the shared, WHATWG-host-inspired
+/// [`host::split_host_port`], then collapses its `Host` back to a plain (unbracketed) string
+/// so existing callers keep working with `address: String`.
+fn split_host_port(host_port: &str) -> Result<(String, &str)> {
+    let (parsed_host, port_str) = host::split_host_port(host_port)?;
+    let address = match parsed_host {
+        Host::Ipv4(ip) => ip.to_string(),
+        Host::Ipv6(ip) => ip.to_string(),
+        Host::Domain(d) => d,
+    };
+    Ok((address, port_str))
+}
+
+impl Trojan {
+    /// Parses a Trojan link with explicit [`ParseOptions`].
+    ///
+    /// In strict mode, invalid percent-encoding in the password or fragment is a
+    /// [`ProtocolError::UrlParseError`] instead of being passed through unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if the link format is invalid or unsupported.
+    pub fn parse_with_options(link: &str, options: ParseOptions) -> Result<Self> {
         if !link.to_lowercase().starts_with(scheme::TROJAN) {
             return Err(ProtocolError::InvalidFormat(format!(
                 "{} {}",
@@ -99,17 +143,10 @@ impl ProtocolParser for Trojan {
             .ok_or_else(|| ProtocolError::InvalidFormat(error_msg::MISSING_AT.to_string()))?;
 
         let password_raw = &main_part[..at_pos];
-        let password = urlencoding::decode(password_raw)
-            .map(|cow| cow.into_owned())
-            .unwrap_or_else(|_| password_raw.to_string());
+        let password = decode_component(password_raw, options, "password")?;
         let host_port = &main_part[at_pos + 1..];
 
-        let colon_pos = host_port.find(':').ok_or_else(|| {
-            ProtocolError::InvalidFormat(error_msg::MISSING_COLON_HOST_PORT.to_string())
-        })?;
-
-        let address = &host_port[..colon_pos];
-        let port_str = &host_port[colon_pos + 1..];
+        let (address, port_str) = split_host_port(host_port)?;
         let port: u16 = port_str.parse().map_err(|e| {
             ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e))
         })?;
@@ -117,7 +154,7 @@ impl ProtocolParser for Trojan {
         // Parse query parameters
         let mut config = TrojanConfig {
             password,
-            address: address.to_string(),
+            address,
             port,
             flow: None,
             security: None,
@@ -126,7 +163,9 @@ impl ProtocolParser for Trojan {
             fp: None,
             r#type: None,
             path: None,
-            remark: fragment.map(|s| urlencoding::decode(s).unwrap_or_default().to_string()),
+            remark: fragment
+                .map(|s| decode_component(s, options, "fragment"))
+                .transpose()?,
         };
 
         if let Some(query) = query_part {
@@ -144,51 +183,215 @@ impl ProtocolParser for Trojan {
             config.path = params.get("path").cloned();
         }
 
+        normalize_idna_fields(&mut config)?;
         Ok(Trojan { config })
     }
 
+    /// Returns `address` in its Unicode display form (reversing IDNA Punycode), unchanged if it
+    /// has no Punycode labels.
+    pub fn address_unicode(&self) -> String {
+        Host::parse(&self.config.address).to_unicode()
+    }
+
+    /// Returns `host` in its Unicode display form (reversing IDNA Punycode), if set.
+    pub fn host_unicode(&self) -> Option<String> {
+        self.config
+            .host
+            .as_deref()
+            .map(|h| Host::parse(h).to_unicode())
+    }
+
+    /// Returns `sni` in its Unicode display form (reversing IDNA Punycode), if set.
+    pub fn sni_unicode(&self) -> Option<String> {
+        self.config
+            .sni
+            .as_deref()
+            .map(|s| Host::parse(s).to_unicode())
+    }
+
+    /// Generates a link like [`ProtocolParser::to_link`], but first normalizes `address`,
+    /// `host`, and `sni` to ASCII so the output is guaranteed ASCII-only even if the
+    /// configuration was built directly (rather than via `parse`, which already normalizes
+    /// these fields).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if a host isn't a valid IDNA host, or other
+    /// `ProtocolError` variants if the configuration cannot be serialized.
+    pub fn to_link_idna(&self) -> Result<String> {
+        let mut config = self.config.clone();
+        normalize_idna_fields(&mut config)?;
+        Trojan { config }.to_link()
+    }
+
+    /// Classifies `address` as an IPv4 literal, IPv6 literal, or domain name, rejecting a
+    /// malformed authority (see [`crate::host::validate_host`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if `address` is neither a valid IP literal nor a
+    /// valid domain name.
+    pub fn address_kind(&self) -> Result<HostKind> {
+        host::validate_host(&self.config.address)
+    }
+
+    /// Parses a Trojan link and additionally checks it with [`Trojan::validate`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if the link format is invalid or unsupported, or
+    /// `ProtocolError::InvalidField` if it fails semantic validation.
+    pub fn parse_strict(link: &str) -> Result<Self> {
+        let trojan = Self::parse(link)?;
+        trojan.validate()?;
+        Ok(trojan)
+    }
+
+    /// Checks semantic correctness beyond what `parse` enforces structurally: `port` isn't 0
+    /// and `address` classifies as a valid IP literal or domain name.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` describing the first check that fails.
+    pub fn validate(&self) -> Result<()> {
+        if self.config.port == 0 {
+            return Err(ProtocolError::InvalidField(
+                "port must be in 1..=65535, got 0".to_string(),
+            ));
+        }
+        self.address_kind()?;
+        Ok(())
+    }
+
+    /// Builds a stable, comparison-only key: see [`crate::Protocol::canonical_key`]. `password`
+    /// is included since it selects a different user on the same server; `remark` is dropped.
+    pub fn canonical_key(&self) -> String {
+        let address = canonical::normalize_host(&self.config.address);
+        let mut params: Vec<(&str, String)> = vec![("password", self.config.password.clone())];
+        if let Some(ref flow) = self.config.flow {
+            params.push(("flow", flow.clone()));
+        }
+        if let Some(ref security) = self.config.security {
+            params.push(("security", security.clone()));
+        }
+        if let Some(ref sni) = self.config.sni {
+            params.push(("sni", canonical::normalize_host(sni)));
+        }
+        if let Some(ref host) = self.config.host {
+            params.push(("host", canonical::normalize_host(host)));
+        }
+        if let Some(ref fp) = self.config.fp {
+            params.push(("fp", fp.clone()));
+        }
+        if let Some(ref r#type) = self.config.r#type {
+            params.push(("type", r#type.clone()));
+        }
+        if let Some(ref path) = self.config.path {
+            params.push(("path", path.clone()));
+        }
+        canonical::build_key("trojan", &address, self.config.port, params)
+    }
+
+    /// Returns a cleaned clone: `address`/`host`/`sni` normalized to ASCII/Punycode and
+    /// lowercased, `remark` cleared. Unlike [`Trojan::canonical_key`] the result is still a
+    /// valid, parseable [`Trojan`].
+    pub fn normalized(&self) -> Self {
+        let mut config = self.config.clone();
+        config.address = canonical::normalize_host(&config.address);
+        config.host = config.host.as_deref().map(canonical::normalize_host);
+        config.sni = config.sni.as_deref().map(canonical::normalize_host);
+        config.remark = None;
+        Trojan { config }
+    }
+}
+
+/// Normalizes `address`, `host`, and `sni` to their ASCII (Punycode) form via IDNA, so a Unicode
+/// hostname pasted into a link is stored and re-emitted in the form other tooling accepts.
+fn normalize_idna_fields(config: &mut TrojanConfig) -> Result<()> {
+    config.address = Host::parse(&config.address).to_ascii()?;
+    if let Some(ref host) = config.host {
+        config.host = Some(Host::parse(host).to_ascii()?);
+    }
+    if let Some(ref sni) = config.sni {
+        config.sni = Some(Host::parse(sni).to_ascii()?);
+    }
+    Ok(())
+}
+
+/// Percent-decodes `raw`; in strict mode an invalid sequence is a `UrlParseError` naming
+/// `field`, in lenient mode the raw input is kept unchanged.
+fn decode_component(raw: &str, options: ParseOptions, field: &str) -> Result<String> {
+    match urlencoding::decode(raw) {
+        Ok(decoded) => Ok(decoded.into_owned()),
+        Err(e) => {
+            if options.strict {
+                Err(ProtocolError::UrlParseError(format!(
+                    "Invalid percent-encoding in {}: {}",
+                    field, e
+                )))
+            } else {
+                Ok(raw.to_string())
+            }
+        }
+    }
+}
+
+impl ProtocolParser for Trojan {
+    fn parse(link: &str) -> Result<Self> {
+        Trojan::parse_with_options(link, ParseOptions::default())
+    }
+
     fn to_link(&self) -> Result<String> {
-        let mut parts = vec![format!(
+        let address = if self.config.address.parse::<Ipv6Addr>().is_ok() {
+            format!("[{}]", self.config.address)
+        } else {
+            self.config.address.clone()
+        };
+
+        let mut link = format!(
             "trojan://{}@{}:{}",
             urlencoding::encode(&self.config.password),
-            self.config.address,
+            address,
             self.config.port
-        )];
+        );
 
-        // Build query string
-        let mut query_params = Vec::new();
+        // Build query string, encoded via the shared `application/x-www-form-urlencoded` codec
+        // so it matches exactly how `parse_with_options` decodes it.
+        let mut pairs: Vec<(&str, &str)> = Vec::new();
 
         if let Some(ref flow) = self.config.flow {
-            query_params.push(format!("flow={}", urlencoding::encode(flow)));
+            pairs.push(("flow", flow));
         }
         if let Some(ref security) = self.config.security {
-            query_params.push(format!("security={}", urlencoding::encode(security)));
+            pairs.push(("security", security));
         }
         if let Some(ref sni) = self.config.sni {
-            query_params.push(format!("sni={}", urlencoding::encode(sni)));
+            pairs.push(("sni", sni));
         }
         if let Some(ref host) = self.config.host {
-            query_params.push(format!("host={}", urlencoding::encode(host)));
+            pairs.push(("host", host));
         }
         if let Some(ref fp) = self.config.fp {
-            query_params.push(format!("fp={}", urlencoding::encode(fp)));
+            pairs.push(("fp", fp));
         }
         if let Some(ref r#type) = self.config.r#type {
-            query_params.push(format!("type={}", urlencoding::encode(r#type)));
+            pairs.push(("type", r#type));
         }
         if let Some(ref path) = self.config.path {
-            query_params.push(format!("path={}", urlencoding::encode(path)));
+            pairs.push(("path", path));
         }
 
-        if !query_params.is_empty() {
-            parts.push(query_params.join("&"));
+        if !pairs.is_empty() {
+            link.push('?');
+            link.push_str(&crate::codec::encode_query(pairs));
         }
 
         // Add fragment (remark)
         if let Some(ref remark) = self.config.remark {
-            parts.push(format!("#{}", urlencoding::encode(remark)));
+            link.push('#');
+            link.push_str(&urlencoding::encode(remark));
         }
 
-        Ok(parts.join("?"))
+        Ok(link)
     }
 }
@@ -13,11 +13,81 @@
 //! 1. Prefix `vless://` is case-insensitive.
 //! 2. Main part must contain exactly one `@` and a `:` for port (`id@address:port`); otherwise `InvalidFormat`.
 //! 3. Port must parse as u16; otherwise `InvalidField`.
+//! 4. [`VLess::parse_with_options`] accepts a [`ParseOptions`] to opt into strict percent-decoding
+//!    of the fragment (see crate-level docs); [`ProtocolParser::parse`] uses the lenient default.
+//! 5. An IPv6 literal address is written bracketed (`[::1]:443`); the brackets are stripped from
+//!    `address` on parse (via the shared [`crate::host::split_host_port`]) and the content is
+//!    validated as a `std::net::Ipv6Addr`, then re-added by `to_link` when `address` parses as
+//!    IPv6.
+//! 6. Query parameters not matched to a known field are kept in `extras` rather than dropped, and
+//!    `to_link` re-emits them after the known parameters so round-tripping an unrecognized
+//!    (e.g. future or vendor-specific) flag doesn't lose it.
+//! 7. [`VLess::parse_strict`] additionally calls [`VLess::validate`], which checks semantic
+//!    correctness `parse` doesn't: `id` is a well-formed UUID, `port` isn't 0, `pbk`/`sid` are
+//!    valid Reality key material, `encryption`/`security`/`type` are in the known sets, and
+//!    `address` classifies as a valid IP literal or domain name ([`VLess::address_kind`]),
+//!    rejecting a malformed authority (empty host, an empty label, a label over 63 bytes, or
+//!    an invalid-looking IPv4 literal such as `1.2.3.999`).
+//! 8. `address`, `host`, and `sni` are normalized to ASCII (Punycode) via IDNA on parse; the
+//!    original Unicode form is available via [`VLess::address_unicode`]/[`VLess::host_unicode`]/
+//!    [`VLess::sni_unicode`], and [`VLess::to_link_idna`] guarantees ASCII-only output even for
+//!    a manually constructed configuration.
+//! 9. `to_link` serializes the query string with the shared `application/x-www-form-urlencoded`
+//!    codec (`crate::codec::encode_query`), the exact inverse of how it's decoded.
 
+use crate::ParseOptions;
 use crate::ProtocolParser;
+use crate::canonical;
 use crate::constants::{error_msg, scheme};
 use crate::error::{ProtocolError, Result};
+use crate::host::{self, Host, HostKind};
+use base64::Engine;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::Ipv6Addr;
+
+/// Query parameter names [`VLess::parse_with_options`] maps to a dedicated [`VLessConfig`] field;
+/// anything else ends up in `extras`.
+const KNOWN_QUERY_KEYS: &[&str] = &[
+    "encryption",
+    "flow",
+    "security",
+    "type",
+    "host",
+    "path",
+    "sni",
+    "fp",
+    "pbk",
+    "sid",
+    "seed",
+    "headerType",
+];
+
+/// Splits a `host:port` segment, treating a leading `[...]` as a bracketed IPv6 literal.
+///
+/// Delegates the actual bracket/IP/domain discipline to the shared, WHATWG-host-inspired
+/// [`host::split_host_port`], then collapses its `Host` back to a plain (unbracketed) string
+/// so existing callers keep working with `address: String`.
+fn split_host_port(host_port: &str) -> Result<(String, &str)> {
+    let (host, port_str) = host::split_host_port(host_port)?;
+    let address = match host {
+        Host::Ipv4(ip) => ip.to_string(),
+        Host::Ipv6(ip) => ip.to_string(),
+        Host::Domain(d) => d,
+    };
+    Ok((address, port_str))
+}
+
+/// Checks whether `s` is a well-formed 8-4-4-4-12 hex UUID (case-insensitive, no braces).
+fn is_valid_uuid(s: &str) -> bool {
+    let groups: Vec<&str> = s.split('-').collect();
+    let expected_lens = [8, 4, 4, 4, 12];
+    groups.len() == expected_lens.len()
+        && groups
+            .iter()
+            .zip(expected_lens)
+            .all(|(g, len)| g.len() == len && g.chars().all(|c| c.is_ascii_hexdigit()))
+}
 
 /// VLess configuration structure
 ///
@@ -69,6 +139,10 @@ pub struct VLessConfig {
     /// Remark/description
     #[serde(skip_serializing_if = "Option::is_none")]
     pub remark: Option<String>,
+    /// Query parameters this crate doesn't map to a dedicated field, kept so `to_link` can
+    /// re-emit them unchanged.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extras: HashMap<String, String>,
 }
 
 /// VLess protocol parser
@@ -78,8 +152,16 @@ pub struct VLess {
     pub config: VLessConfig,
 }
 
-impl ProtocolParser for VLess {
-    fn parse(link: &str) -> Result<Self> {
+impl VLess {
+    /// Parses a VLess link with explicit [`ParseOptions`].
+    ///
+    /// In strict mode, invalid percent-encoding in the fragment is a
+    /// [`ProtocolError::UrlParseError`] instead of being passed through unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if the link format is invalid or unsupported.
+    pub fn parse_with_options(link: &str, options: ParseOptions) -> Result<Self> {
         if !link.to_lowercase().starts_with(scheme::VLESS) {
             return Err(ProtocolError::InvalidFormat(format!(
                 "{} {}",
@@ -117,12 +199,7 @@ impl ProtocolParser for VLess {
         let id = &main_part[..at_pos];
         let host_port = &main_part[at_pos + 1..];
 
-        let colon_pos = host_port.find(':').ok_or_else(|| {
-            ProtocolError::InvalidFormat(error_msg::MISSING_COLON_HOST_PORT.to_string())
-        })?;
-
-        let address = &host_port[..colon_pos];
-        let port_str = &host_port[colon_pos + 1..];
+        let (address, port_str) = split_host_port(host_port)?;
         let port: u16 = port_str.parse().map_err(|e| {
             ProtocolError::InvalidField(format!("{}: {}", error_msg::INVALID_PORT, e))
         })?;
@@ -130,7 +207,7 @@ impl ProtocolParser for VLess {
         // Parse query parameters
         let mut config = VLessConfig {
             id: id.to_string(),
-            address: address.to_string(),
+            address,
             port,
             encryption: None,
             flow: None,
@@ -144,14 +221,16 @@ impl ProtocolParser for VLess {
             sid: None,
             seed: None,
             header_type: None,
-            remark: fragment.map(|s| urlencoding::decode(s).unwrap_or_default().to_string()),
+            remark: fragment
+                .map(|s| decode_fragment(s, options))
+                .transpose()?,
+            extras: HashMap::new(),
         };
 
         if let Some(query) = query_part {
-            let params: std::collections::HashMap<String, String> =
-                url::form_urlencoded::parse(query.as_bytes())
-                    .into_owned()
-                    .collect();
+            let params: HashMap<String, String> = url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect();
 
             config.encryption = params.get("encryption").cloned();
             config.flow = params.get("flow").cloned();
@@ -165,66 +244,316 @@ impl ProtocolParser for VLess {
             config.sid = params.get("sid").cloned();
             config.seed = params.get("seed").cloned();
             config.header_type = params.get("headerType").cloned();
+
+            config.extras = params
+                .into_iter()
+                .filter(|(k, _)| !KNOWN_QUERY_KEYS.contains(&k.as_str()))
+                .collect();
         }
 
+        normalize_idna_fields(&mut config)?;
         Ok(VLess { config })
     }
 
+    /// Parses a VLess link and additionally checks it with [`VLess::validate`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError` if the link format is invalid or unsupported, or
+    /// `ProtocolError::InvalidField` if it fails semantic validation.
+    pub fn parse_strict(link: &str) -> Result<Self> {
+        let vless = Self::parse(link)?;
+        vless.validate()?;
+        Ok(vless)
+    }
+
+    /// Checks semantic correctness beyond what `parse` enforces structurally: `id` is a
+    /// well-formed UUID, `port` isn't 0, `pbk`/`sid` (Reality) are valid key material,
+    /// `encryption`/`security`/`type` are in the known value sets, and `address` classifies as
+    /// a valid IP literal or domain name (see [`VLess::address_kind`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` describing the first check that fails.
+    pub fn validate(&self) -> Result<()> {
+        if !is_valid_uuid(&self.config.id) {
+            return Err(ProtocolError::InvalidField(format!(
+                "id is not a well-formed UUID: {}",
+                self.config.id
+            )));
+        }
+        if self.config.port == 0 {
+            return Err(ProtocolError::InvalidField(
+                "port must be in 1..=65535, got 0".to_string(),
+            ));
+        }
+        match self.config.encryption.as_deref() {
+            Some(encryption) if encryption != "none" => {
+                return Err(ProtocolError::InvalidField(format!(
+                    "Unknown encryption method: {}",
+                    encryption
+                )));
+            }
+            _ => {}
+        }
+        if let Some(ref security) = self.config.security {
+            const KNOWN_SECURITY: &[&str] = &["tls", "xtls", "reality", "none"];
+            if !KNOWN_SECURITY.contains(&security.as_str()) {
+                return Err(ProtocolError::InvalidField(format!(
+                    "Unknown security type: {}",
+                    security
+                )));
+            }
+        }
+        if let Some(ref net_type) = self.config.r#type {
+            const KNOWN_TYPE: &[&str] =
+                &["tcp", "kcp", "ws", "h2", "quic", "grpc", "multi", "httpupgrade"];
+            if !KNOWN_TYPE.contains(&net_type.as_str()) {
+                return Err(ProtocolError::InvalidField(format!(
+                    "Unknown network type: {}",
+                    net_type
+                )));
+            }
+        }
+        if let Some(ref pbk) = self.config.pbk {
+            let decoded = base64::engine::general_purpose::URL_SAFE_NO_PAD
+                .decode(pbk)
+                .or_else(|_| base64::engine::general_purpose::STANDARD_NO_PAD.decode(pbk))
+                .map_err(|e| {
+                    ProtocolError::InvalidField(format!("pbk is not valid Base64: {}", e))
+                })?;
+            if decoded.len() != 32 {
+                return Err(ProtocolError::InvalidField(format!(
+                    "pbk must decode to a 32-byte Reality public key, got {} bytes",
+                    decoded.len()
+                )));
+            }
+        }
+        match self.config.sid.as_deref() {
+            Some(sid) if sid.len() > 16 || !sid.chars().all(|c| c.is_ascii_hexdigit()) => {
+                return Err(ProtocolError::InvalidField(format!(
+                    "sid must be a hex string of at most 16 characters: {}",
+                    sid
+                )));
+            }
+            _ => {}
+        }
+        self.address_kind()?;
+        Ok(())
+    }
+
+    /// Builds a stable, comparison-only key: see [`crate::Protocol::canonical_key`]. `id` is
+    /// included since it selects a different user on the same server; `remark` and `extras` are
+    /// dropped.
+    pub fn canonical_key(&self) -> String {
+        let address = canonical::normalize_host(&self.config.address);
+        let mut params: Vec<(&str, String)> = vec![("id", self.config.id.clone())];
+        if let Some(ref encryption) = self.config.encryption {
+            params.push(("encryption", encryption.clone()));
+        }
+        if let Some(ref flow) = self.config.flow {
+            params.push(("flow", flow.clone()));
+        }
+        if let Some(ref security) = self.config.security {
+            params.push(("security", security.clone()));
+        }
+        if let Some(ref r#type) = self.config.r#type {
+            params.push(("type", r#type.clone()));
+        }
+        if let Some(ref host) = self.config.host {
+            params.push(("host", canonical::normalize_host(host)));
+        }
+        if let Some(ref path) = self.config.path {
+            params.push(("path", path.clone()));
+        }
+        if let Some(ref sni) = self.config.sni {
+            params.push(("sni", canonical::normalize_host(sni)));
+        }
+        if let Some(ref fp) = self.config.fp {
+            params.push(("fp", fp.clone()));
+        }
+        if let Some(ref pbk) = self.config.pbk {
+            params.push(("pbk", pbk.clone()));
+        }
+        if let Some(ref sid) = self.config.sid {
+            params.push(("sid", sid.clone()));
+        }
+        if let Some(ref seed) = self.config.seed {
+            params.push(("seed", seed.clone()));
+        }
+        if let Some(ref header_type) = self.config.header_type {
+            params.push(("headerType", header_type.clone()));
+        }
+        canonical::build_key("vless", &address, self.config.port, params)
+    }
+
+    /// Returns a cleaned clone: `address`/`host`/`sni` normalized to ASCII/Punycode and
+    /// lowercased, `remark` cleared. Unlike [`VLess::canonical_key`] the result is still a
+    /// valid, parseable [`VLess`].
+    pub fn normalized(&self) -> Self {
+        let mut config = self.config.clone();
+        config.address = canonical::normalize_host(&config.address);
+        config.host = config.host.as_deref().map(canonical::normalize_host);
+        config.sni = config.sni.as_deref().map(canonical::normalize_host);
+        config.remark = None;
+        VLess { config }
+    }
+
+    /// Classifies `address` as an IPv4 literal, IPv6 literal, or domain name, rejecting a
+    /// malformed authority (see [`crate::host::validate_host`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if `address` is neither a valid IP literal nor a
+    /// valid domain name.
+    pub fn address_kind(&self) -> Result<HostKind> {
+        host::validate_host(&self.config.address)
+    }
+
+    /// Returns `address` in its Unicode display form (reversing IDNA Punycode), unchanged if it
+    /// has no Punycode labels.
+    pub fn address_unicode(&self) -> String {
+        Host::parse(&self.config.address).to_unicode()
+    }
+
+    /// Returns `host` in its Unicode display form (reversing IDNA Punycode), if set.
+    pub fn host_unicode(&self) -> Option<String> {
+        self.config
+            .host
+            .as_deref()
+            .map(|h| Host::parse(h).to_unicode())
+    }
+
+    /// Returns `sni` in its Unicode display form (reversing IDNA Punycode), if set.
+    pub fn sni_unicode(&self) -> Option<String> {
+        self.config
+            .sni
+            .as_deref()
+            .map(|s| Host::parse(s).to_unicode())
+    }
+
+    /// Generates a link like [`ProtocolParser::to_link`], but first normalizes `address`,
+    /// `host`, and `sni` to ASCII so the output is guaranteed ASCII-only even if the
+    /// configuration was built directly (rather than via `parse`, which already normalizes
+    /// these fields).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ProtocolError::InvalidField` if a host isn't a valid IDNA host, or other
+    /// `ProtocolError` variants if the configuration cannot be serialized.
+    pub fn to_link_idna(&self) -> Result<String> {
+        let mut config = self.config.clone();
+        normalize_idna_fields(&mut config)?;
+        VLess { config }.to_link()
+    }
+}
+
+/// Normalizes `address`, `host`, and `sni` to their ASCII (Punycode) form via IDNA, so a Unicode
+/// hostname pasted into a link is stored and re-emitted in the form other tooling accepts.
+fn normalize_idna_fields(config: &mut VLessConfig) -> Result<()> {
+    config.address = Host::parse(&config.address).to_ascii()?;
+    if let Some(ref host) = config.host {
+        config.host = Some(Host::parse(host).to_ascii()?);
+    }
+    if let Some(ref sni) = config.sni {
+        config.sni = Some(Host::parse(sni).to_ascii()?);
+    }
+    Ok(())
+}
+
+/// Percent-decodes a fragment; in strict mode an invalid sequence is a `UrlParseError`, in
+/// lenient mode the raw input is kept unchanged.
+fn decode_fragment(raw: &str, options: ParseOptions) -> Result<String> {
+    match urlencoding::decode(raw) {
+        Ok(decoded) => Ok(decoded.into_owned()),
+        Err(e) => {
+            if options.strict {
+                Err(ProtocolError::UrlParseError(format!(
+                    "Invalid percent-encoding in fragment: {}",
+                    e
+                )))
+            } else {
+                Ok(raw.to_string())
+            }
+        }
+    }
+}
+
+impl ProtocolParser for VLess {
+    fn parse(link: &str) -> Result<Self> {
+        VLess::parse_with_options(link, ParseOptions::default())
+    }
+
     fn to_link(&self) -> Result<String> {
-        let mut parts = vec![format!(
+        let address = if self.config.address.parse::<Ipv6Addr>().is_ok() {
+            format!("[{}]", self.config.address)
+        } else {
+            self.config.address.clone()
+        };
+
+        let mut link = format!(
             "vless://{}@{}:{}",
-            self.config.id, self.config.address, self.config.port
-        )];
+            self.config.id, address, self.config.port
+        );
 
-        // Build query string
-        let mut query_params = Vec::new();
+        // Build query string, encoded via the shared `application/x-www-form-urlencoded` codec
+        // so it matches exactly how `parse_with_options` decodes it.
+        let mut pairs: Vec<(&str, &str)> = Vec::new();
 
         if let Some(ref encryption) = self.config.encryption {
-            query_params.push(format!("encryption={}", urlencoding::encode(encryption)));
+            pairs.push(("encryption", encryption));
         }
         if let Some(ref flow) = self.config.flow {
-            query_params.push(format!("flow={}", urlencoding::encode(flow)));
+            pairs.push(("flow", flow));
         }
         if let Some(ref security) = self.config.security {
-            query_params.push(format!("security={}", urlencoding::encode(security)));
+            pairs.push(("security", security));
         }
         if let Some(ref r#type) = self.config.r#type {
-            query_params.push(format!("type={}", urlencoding::encode(r#type)));
+            pairs.push(("type", r#type));
         }
         if let Some(ref host) = self.config.host {
-            query_params.push(format!("host={}", urlencoding::encode(host)));
+            pairs.push(("host", host));
         }
         if let Some(ref path) = self.config.path {
-            query_params.push(format!("path={}", urlencoding::encode(path)));
+            pairs.push(("path", path));
         }
         if let Some(ref sni) = self.config.sni {
-            query_params.push(format!("sni={}", urlencoding::encode(sni)));
+            pairs.push(("sni", sni));
         }
         if let Some(ref fp) = self.config.fp {
-            query_params.push(format!("fp={}", urlencoding::encode(fp)));
+            pairs.push(("fp", fp));
         }
         if let Some(ref pbk) = self.config.pbk {
-            query_params.push(format!("pbk={}", urlencoding::encode(pbk)));
+            pairs.push(("pbk", pbk));
         }
         if let Some(ref sid) = self.config.sid {
-            query_params.push(format!("sid={}", urlencoding::encode(sid)));
+            pairs.push(("sid", sid));
         }
         if let Some(ref seed) = self.config.seed {
-            query_params.push(format!("seed={}", urlencoding::encode(seed)));
+            pairs.push(("seed", seed));
         }
         if let Some(ref header_type) = self.config.header_type {
-            query_params.push(format!("headerType={}", urlencoding::encode(header_type)));
+            pairs.push(("headerType", header_type));
+        }
+
+        let mut extras: Vec<_> = self.config.extras.iter().collect();
+        extras.sort_by_key(|(key, _)| key.as_str());
+        for (key, value) in extras {
+            pairs.push((key.as_str(), value.as_str()));
         }
 
-        if !query_params.is_empty() {
-            parts.push(query_params.join("&"));
+        if !pairs.is_empty() {
+            link.push('?');
+            link.push_str(&crate::codec::encode_query(pairs));
         }
 
         // Add fragment (remark)
         if let Some(ref remark) = self.config.remark {
-            parts.push(format!("#{}", urlencoding::encode(remark)));
+            link.push('#');
+            link.push_str(&urlencoding::encode(remark));
         }
 
-        Ok(parts.join("?"))
+        Ok(link)
     }
 }